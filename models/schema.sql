@@ -0,0 +1,167 @@
+-- The ISPyB tables from which entities are regenerated in the absence of a DATABASE_URL, as dumped by
+-- `mysqldump --no-data`; tables and columns not selected in tables.yaml are ignored
+--
+-- Views are written with the definitions of their columns, as reported by `information_schema.columns`, in place of
+-- their queries, as mysqldump does not include the types of their columns
+
+CREATE TABLE `Person` (
+  `personId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `familyName` varchar(100) DEFAULT NULL,
+  `givenName` varchar(45) DEFAULT NULL,
+  `title` varchar(45) DEFAULT NULL,
+  `emailAddress` varchar(60) DEFAULT NULL,
+  `login` varchar(45) DEFAULT NULL,
+  PRIMARY KEY (`personId`),
+  UNIQUE KEY `Person_FKIndex_Login` (`login`),
+  KEY `Person_FKIndexFamilyName` (`familyName`)
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `Proposal` (
+  `proposalId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `personId` int(10) unsigned NOT NULL DEFAULT 0,
+  `title` varchar(200) DEFAULT NULL,
+  `proposalCode` varchar(45) DEFAULT NULL,
+  `proposalNumber` varchar(45) DEFAULT NULL,
+  `proposalType` varchar(2) DEFAULT NULL COMMENT 'Proposal type: MX, BX',
+  `state` enum('Open','Closed','Cancelled') DEFAULT 'Open',
+  PRIMARY KEY (`proposalId`),
+  UNIQUE KEY `Proposal_FKIndexCodeNumber` (`proposalCode`,`proposalNumber`),
+  KEY `Proposal_FKIndexPerson` (`personId`),
+  CONSTRAINT `Proposal_ibfk_1` FOREIGN KEY (`personId`) REFERENCES `Person` (`personId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `BLSession` (
+  `sessionId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `proposalId` int(10) unsigned NOT NULL DEFAULT 0,
+  `startDate` datetime DEFAULT NULL,
+  `endDate` datetime DEFAULT NULL,
+  `visit_number` int(10) unsigned DEFAULT 0,
+  `beamLineName` varchar(45) DEFAULT NULL,
+  `usedFlag` tinyint(1) DEFAULT NULL COMMENT 'indicates if session has Datacollections or XFE or EnergyScans attached',
+  PRIMARY KEY (`sessionId`),
+  KEY `proposalId` (`proposalId`),
+  CONSTRAINT `BLSession_ibfk_1` FOREIGN KEY (`proposalId`) REFERENCES `Proposal` (`proposalId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `Session_has_Person` (
+  `sessionId` int(10) unsigned NOT NULL DEFAULT 0,
+  `personId` int(10) unsigned NOT NULL DEFAULT 0,
+  `role` enum('Local Contact','Local Contact 2','Staff','Team Leader','Co-Investigator','Principal Investigator','Alternate Contact','Data Access','Team Member','ERA Admin','Associate') DEFAULT NULL,
+  `remote` tinyint(1) DEFAULT 0,
+  PRIMARY KEY (`sessionId`,`personId`),
+  KEY `Session_has_Person_FKIndex2` (`personId`),
+  CONSTRAINT `Session_has_Person_ibfk_1` FOREIGN KEY (`sessionId`) REFERENCES `BLSession` (`sessionId`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `Session_has_Person_ibfk_2` FOREIGN KEY (`personId`) REFERENCES `Person` (`personId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `DataCollectionGroup` (
+  `dataCollectionGroupId` int(11) NOT NULL AUTO_INCREMENT COMMENT 'Primary key (auto-incremented)',
+  `sessionId` int(10) unsigned NOT NULL COMMENT 'references Session table',
+  `comments` varchar(1024) DEFAULT NULL COMMENT 'comments',
+  `startTime` datetime DEFAULT NULL COMMENT 'Start time of the dataCollectionGroup',
+  `endTime` datetime DEFAULT NULL COMMENT 'end time of the dataCollectionGroup',
+  PRIMARY KEY (`dataCollectionGroupId`),
+  KEY `DataCollectionGroup_FKIndex1` (`sessionId`),
+  CONSTRAINT `DataCollectionGroup_ibfk_1` FOREIGN KEY (`sessionId`) REFERENCES `BLSession` (`sessionId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1 COMMENT='a dataCollectionGroup is a group of dataCollection for a spe';
+
+CREATE TABLE `DataCollection` (
+  `dataCollectionId` int(11) unsigned NOT NULL AUTO_INCREMENT COMMENT 'Primary key (auto-incremented)',
+  `dataCollectionGroupId` int(11) NOT NULL COMMENT 'references DataCollectionGroup table',
+  `dataCollectionNumber` int(10) unsigned DEFAULT NULL,
+  `startTime` datetime DEFAULT NULL COMMENT 'Start time of the dataCollection',
+  `endTime` datetime DEFAULT NULL COMMENT 'end time of the dataCollection',
+  `runStatus` varchar(255) DEFAULT NULL,
+  `numberOfImages` int(10) unsigned DEFAULT NULL,
+  `exposureTime` float DEFAULT NULL,
+  `wavelength` float DEFAULT NULL,
+  `imageDirectory` varchar(255) DEFAULT NULL COMMENT 'The directory where files reside - should end with a slash',
+  `imagePrefix` varchar(45) DEFAULT NULL,
+  `fileTemplate` varchar(255) DEFAULT NULL,
+  `comments` varchar(1024) DEFAULT NULL,
+  PRIMARY KEY (`dataCollectionId`),
+  KEY `DataCollection_FKIndex1` (`dataCollectionGroupId`),
+  CONSTRAINT `DataCollection_ibfk_1` FOREIGN KEY (`dataCollectionGroupId`) REFERENCES `DataCollectionGroup` (`dataCollectionGroupId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `Shipping` (
+  `shippingId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `proposalId` int(10) unsigned NOT NULL DEFAULT 0,
+  `shippingName` varchar(45) DEFAULT NULL,
+  `shippingStatus` varchar(45) DEFAULT NULL,
+  `creationDate` datetime DEFAULT NULL,
+  `comments` varchar(1000) DEFAULT NULL,
+  PRIMARY KEY (`shippingId`),
+  KEY `Shipping_FKIndex1` (`proposalId`),
+  CONSTRAINT `Shipping_ibfk_1` FOREIGN KEY (`proposalId`) REFERENCES `Proposal` (`proposalId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `Dewar` (
+  `dewarId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `shippingId` int(10) unsigned DEFAULT NULL,
+  `code` varchar(45) DEFAULT NULL,
+  `comments` tinytext DEFAULT NULL,
+  `barCode` varchar(45) DEFAULT NULL,
+  `dewarStatus` varchar(45) DEFAULT NULL,
+  `firstExperimentId` int(10) unsigned DEFAULT NULL COMMENT 'Indicates the first experiment for this dewar',
+  PRIMARY KEY (`dewarId`),
+  UNIQUE KEY `barCode` (`barCode`),
+  KEY `Dewar_FKIndex1` (`shippingId`),
+  KEY `Dewar_FKIndexStatus` (`dewarStatus`),
+  KEY `Dewar_fk_firstExperimentId` (`firstExperimentId`),
+  CONSTRAINT `Dewar_fk_firstExperimentId` FOREIGN KEY (`firstExperimentId`) REFERENCES `BLSession` (`sessionId`) ON DELETE NO ACTION ON UPDATE CASCADE,
+  CONSTRAINT `Dewar_ibfk_1` FOREIGN KEY (`shippingId`) REFERENCES `Shipping` (`shippingId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `Container` (
+  `containerId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `dewarId` int(10) unsigned DEFAULT NULL,
+  `code` varchar(45) DEFAULT NULL,
+  `containerType` varchar(20) DEFAULT NULL,
+  `capacity` int(10) DEFAULT NULL,
+  `barcode` varchar(45) DEFAULT NULL,
+  `containerStatus` varchar(45) DEFAULT NULL,
+  `sessionId` int(10) unsigned DEFAULT NULL,
+  PRIMARY KEY (`containerId`),
+  UNIQUE KEY `Container_UNIndex1` (`barcode`),
+  KEY `Container_FKIndex1` (`dewarId`),
+  KEY `Container_FKIndexStatus` (`containerStatus`),
+  KEY `Container_ibfk6` (`sessionId`),
+  CONSTRAINT `Container_ibfk5` FOREIGN KEY (`dewarId`) REFERENCES `Dewar` (`dewarId`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `Container_ibfk6` FOREIGN KEY (`sessionId`) REFERENCES `BLSession` (`sessionId`) ON DELETE SET NULL ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `BLSample` (
+  `blSampleId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `containerId` int(10) unsigned DEFAULT NULL,
+  `name` varchar(45) DEFAULT NULL,
+  `code` varchar(45) DEFAULT NULL,
+  `location` varchar(45) DEFAULT NULL,
+  `comments` varchar(1024) DEFAULT NULL,
+  PRIMARY KEY (`blSampleId`),
+  KEY `BLSample_FKIndex1` (`containerId`),
+  KEY `BLSample_Index1` (`name`) USING BTREE,
+  CONSTRAINT `BLSample_ibfk_1` FOREIGN KEY (`containerId`) REFERENCES `Container` (`containerId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE TABLE `SessionType` (
+  `sessionTypeId` int(10) unsigned NOT NULL AUTO_INCREMENT,
+  `sessionId` int(10) unsigned NOT NULL,
+  `typeName` varchar(31) NOT NULL,
+  PRIMARY KEY (`sessionTypeId`),
+  KEY `SessionType_FKIndex1` (`sessionId`),
+  CONSTRAINT `SessionType_ibfk_1` FOREIGN KEY (`sessionId`) REFERENCES `BLSession` (`sessionId`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=latin1;
+
+CREATE VIEW `v_session` (
+  `sessionId` int(10) unsigned NOT NULL DEFAULT 0,
+  `proposalId` int(10) unsigned NOT NULL DEFAULT 0,
+  `BLSession_startDate` datetime DEFAULT NULL,
+  `BLSession_endDate` datetime DEFAULT NULL,
+  `beamLineName` varchar(45) DEFAULT NULL,
+  `visit_number` int(10) unsigned DEFAULT 0,
+  `Proposal_proposalId` int(10) unsigned DEFAULT 0,
+  `proposalCode` varchar(45) DEFAULT NULL,
+  `proposalNumber` varchar(45) DEFAULT NULL,
+  `title` varchar(200) DEFAULT NULL
+);
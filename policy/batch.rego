@@ -0,0 +1,14 @@
+package batch
+
+import data.system
+import rego.v1
+
+# METADATA
+# description: Allow decisions for each of the requested parameters, in order
+# entrypoint: true
+main := {"allowed": allowed}
+
+allowed := [decision |
+	some parameters in input.parameters
+	decision := system.allow with input.parameters as parameters
+]
@@ -0,0 +1,18 @@
+package public
+
+import data.system
+import rego.v1
+
+# METADATA
+# description: Allow authenticated subjects to read non-sensitive metadata
+# entrypoint: true
+read := {"allow": allow}
+
+default allow := false
+
+allow if system.subject
+
+# METADATA
+# description: Allow anyone, including unauthenticated requests, to read non-sensitive metadata
+# entrypoint: true
+anonymous := {"allow": true}
@@ -0,0 +1,9 @@
+package sessions
+
+import data.system
+import rego.v1
+
+# METADATA
+# description: Allow reading of a session by subjects on the session or containing proposal
+# entrypoint: true
+read := system.main
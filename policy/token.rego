@@ -25,4 +25,7 @@ valid := io.jwt.decode_verify(input.token, {
 	"aud": "account",
 })
 
-claims := valid[2]
+# Claims validated by the service, if forwarded in place of the token
+claims := input.claims
+
+claims := valid[2] if not input.claims
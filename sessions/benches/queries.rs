@@ -0,0 +1,62 @@
+//! Benchmarks which drive a server, serving the development database, with the queries made of it in production, both
+//! alone and as a concurrent mix, such that regressions in the resolvers are measured before release
+//!
+//! Run them with `cargo bench --bench queries`, comparing against a baseline saved with `-- --save-baseline <NAME>`
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::Server;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::future::join_all;
+
+/// The queries made of the service, by their name and the number of times each is made in the concurrent mix, in
+/// proportion to how often it is made in production
+const QUERIES: &[(&str, usize, &str)] = &[
+    (
+        "session",
+        12,
+        r#"{ session(proposalCode: "cm", proposalNumber: 31111, visit: 1) { id visit start end beamline proposal { code number state } } }"#,
+    ),
+    (
+        "sessions",
+        2,
+        "{ sessions { id visit beamline proposal { code number } } }",
+    ),
+    (
+        "sessions_of_proposal",
+        4,
+        r#"{ sessions(proposalCode: "mx", proposalNumber: 23694) { id visit start end } }"#,
+    ),
+    ("beamlines", 1, "{ beamlines }"),
+    ("session_count", 1, r#"{ sessionCount(beamline: "i03") }"#),
+];
+
+/// Measures the latency of each query alone, and the throughput of the mix of queries made concurrently
+fn queries(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = runtime.block_on(Server::start(["--dev"]));
+
+    let mut alone = c.benchmark_group("query");
+    for (name, _, query) in QUERIES {
+        alone.bench_function(*name, |b| {
+            b.to_async(&runtime).iter(|| server.execute(query))
+        });
+    }
+    alone.finish();
+
+    let mix = QUERIES
+        .iter()
+        .flat_map(|(_, weight, query)| vec![*query; *weight])
+        .collect::<Vec<_>>();
+    let mut concurrent = c.benchmark_group("mix");
+    concurrent.throughput(Throughput::Elements(mix.len() as u64));
+    concurrent.bench_function("concurrent", |b| {
+        b.to_async(&runtime)
+            .iter(|| join_all(mix.iter().map(|query| server.execute(query))))
+    });
+    concurrent.finish();
+}
+
+criterion_group!(benches, queries);
+criterion_main!(benches);
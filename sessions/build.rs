@@ -1,3 +1,25 @@
+use std::process::Command;
+
 fn main() {
     built::write_built_file().unwrap();
+    let commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .map(|commit| commit.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT={commit}");
+    println!("cargo:rustc-check-cfg=cfg(tokio_unstable)");
+    let protos = protox::compile(["sessions.proto"], ["proto"]).unwrap();
+    prost_build::Config::new()
+        .service_generator(tonic_build::configure().service_generator())
+        .compile_fds(protos)
+        .unwrap();
 }
@@ -0,0 +1,46 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+use tracing::info;
+
+/// The identity of a machine client, authenticated by an API key or service token
+#[derive(Debug, Clone)]
+pub struct ServiceIdentity(pub String);
+
+/// An entry in the API key file
+#[derive(Debug, Deserialize)]
+struct ApiKeyEntry {
+    /// The service identity the key authenticates
+    identity: String,
+    /// The hex encoded SHA-256 digest of the key
+    key_sha256: String,
+}
+
+/// A set of API keys, each mapped to a [`ServiceIdentity`]
+///
+/// Only digests of the keys are held, such that the key file need not contain the keys themselves
+#[derive(Debug)]
+pub struct ApiKeys(HashMap<[u8; 32], String>);
+
+impl ApiKeys {
+    /// Loads the API keys from a JSON file containing a list of `identity` and `key_sha256` pairs
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let entries: Vec<ApiKeyEntry> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        let keys = entries
+            .into_iter()
+            .map(|entry| {
+                let mut digest = [0; 32];
+                hex::decode_to_slice(&entry.key_sha256, &mut digest)?;
+                Ok((digest, entry.identity))
+            })
+            .collect::<Result<HashMap<_, _>, anyhow::Error>>()?;
+        info!("Loaded {} API keys from {}", keys.len(), path.display());
+        Ok(Self(keys))
+    }
+
+    /// Finds the [`ServiceIdentity`] authenticated by the provided key, if any
+    pub fn identify(&self, key: &str) -> Option<ServiceIdentity> {
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        self.0.get(&digest).cloned().map(ServiceIdentity)
+    }
+}
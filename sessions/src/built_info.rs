@@ -1 +1,24 @@
 include!(concat!(env!("OUT_DIR"), "/built.rs"));
+
+/// The commit the crate was built from, taken from the `GIT_COMMIT` environment variable or the repository, if known
+pub const GIT_COMMIT: &str = env!("GIT_COMMIT");
+
+/// Exports a `build_info` gauge, always one, labelled with the version, commit and compiler the service was built with
+pub fn export_build_info() {
+    opentelemetry::global::meter(PKG_NAME)
+        .u64_observable_gauge("build_info")
+        .with_description("The version, commit and compiler the service was built with")
+        .with_callback(
+            |observer: &dyn opentelemetry::metrics::AsyncInstrument<u64>| {
+                observer.observe(
+                    1,
+                    &[
+                        opentelemetry::KeyValue::new("version", PKG_VERSION),
+                        opentelemetry::KeyValue::new("commit", GIT_COMMIT),
+                        opentelemetry::KeyValue::new("rustc", RUSTC_VERSION),
+                    ],
+                );
+            },
+        )
+        .init();
+}
@@ -0,0 +1,188 @@
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt::Debug, future::Future, pin::Pin, sync::Arc, time::Duration};
+use tracing::{info, instrument, warn};
+use url::Url;
+
+/// The key under which a value derived from the material is cached, being the SHA-256 digest of its serialization such
+/// that tokens and other credentials within the material are never written to the backend
+pub fn digest_key(material: &impl Serialize) -> Result<String, serde_json::Error> {
+    serde_json::to_vec(material).map(|material| hex::encode(Sha256::digest(material)))
+}
+
+/// A boxed [`Future`] returned by [`CacheBackend`] operations
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A store of serialized values, from which entries may be evicted at any time
+///
+/// Failures of the store are logged and treated as misses, such that an unavailable cache never fails a request
+pub trait CacheBackend: Debug + Send + Sync {
+    /// Retrieves the value cached under the key, if any
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<u8>>>;
+    /// Caches the value under the key
+    fn insert(&self, key: String, value: Vec<u8>) -> BoxFuture<'_, ()>;
+    /// Evicts all cached values
+    fn clear(&self) -> BoxFuture<'_, ()>;
+}
+
+/// A bounded in-process [`CacheBackend`], private to each replica of the service
+#[derive(Debug)]
+pub struct MemoryCache(moka::future::Cache<String, Vec<u8>>);
+
+impl MemoryCache {
+    /// Creates an empty [`MemoryCache`] retaining up to the capacity of values for the ttl
+    pub fn new(ttl: Duration, capacity: u64) -> Self {
+        Self(
+            moka::future::Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(capacity)
+                .build(),
+        )
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<u8>>> {
+        Box::pin(self.0.get(key))
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>) -> BoxFuture<'_, ()> {
+        Box::pin(self.0.insert(key, value))
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        self.0.invalidate_all();
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// A [`CacheBackend`] in Redis, shared between replicas of the service, with keys in a namespace
+#[derive(Clone)]
+pub struct RedisCache {
+    /// A multiplexed connection to Redis, re-established on failure
+    connection: ConnectionManager,
+    /// The prefix of all keys in this cache
+    namespace: String,
+    /// How long each value is retained
+    ttl: Duration,
+}
+
+impl Debug for RedisCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisCache")
+            .field("namespace", &self.namespace)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisCache {
+    /// Connects to Redis at the [`Url`], returning a connection which may be shared by caches in distinct namespaces
+    #[instrument]
+    pub async fn connect(url: &Url) -> Result<ConnectionManager, redis::RedisError> {
+        info!("Connecting to Redis cache at {url}");
+        redis::Client::open(url.as_str())?
+            .get_connection_manager()
+            .await
+    }
+
+    /// Creates a [`RedisCache`] of values in the namespace, retained for the ttl
+    pub fn new(connection: ConnectionManager, namespace: &str, ttl: Duration) -> Self {
+        Self {
+            connection,
+            namespace: namespace.to_string(),
+            ttl,
+        }
+    }
+}
+
+impl CacheBackend for RedisCache {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            self.connection
+                .clone()
+                .get::<_, Option<Vec<u8>>>(format!("{}:{key}", self.namespace))
+                .await
+                .unwrap_or_else(|err| {
+                    warn!("Could not read from Redis cache: {err}");
+                    None
+                })
+        })
+    }
+
+    fn insert(&self, key: String, value: Vec<u8>) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            if let Err(err) = self
+                .connection
+                .clone()
+                .set_ex::<_, _, ()>(
+                    format!("{}:{key}", self.namespace),
+                    value,
+                    self.ttl.as_secs().max(1),
+                )
+                .await
+            {
+                warn!("Could not write to Redis cache: {err}");
+            }
+        })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            let mut keys = Vec::new();
+            match connection
+                .scan_match::<_, String>(format!("{}:*", self.namespace))
+                .await
+            {
+                Ok(mut iter) => {
+                    while let Some(key) = iter.next_item().await {
+                        keys.push(key);
+                    }
+                }
+                Err(err) => {
+                    warn!("Could not list Redis cache keys: {err}");
+                    return;
+                }
+            }
+            if !keys.is_empty() {
+                if let Err(err) = connection.del::<_, ()>(keys).await {
+                    warn!("Could not clear Redis cache: {err}");
+                }
+            }
+        })
+    }
+}
+
+/// A cache of serializable values, held by a [`CacheBackend`]
+#[derive(Debug, Clone)]
+pub struct Cache(Arc<dyn CacheBackend>);
+
+impl Cache {
+    /// Creates a [`Cache`] held by the backend
+    pub fn new(backend: impl CacheBackend + 'static) -> Self {
+        Self(Arc::new(backend))
+    }
+
+    /// Retrieves the value cached under the key, if any
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.0.get(key).await?;
+        serde_json::from_slice(&value)
+            .inspect_err(|err| warn!("Could not deserialize cached value: {err}"))
+            .ok()
+    }
+
+    /// Caches the value under the key
+    pub async fn insert<T: Serialize>(&self, key: String, value: &T) {
+        match serde_json::to_vec(value) {
+            Ok(value) => self.0.insert(key, value).await,
+            Err(err) => warn!("Could not serialize value for caching: {err}"),
+        }
+    }
+
+    /// Evicts all cached values
+    pub async fn clear(&self) {
+        self.0.clear().await
+    }
+}
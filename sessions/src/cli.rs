@@ -0,0 +1,1573 @@
+use crate::{
+    api_key::ApiKeys,
+    built_info,
+    cache::{Cache, MemoryCache, RedisCache},
+    config_file,
+    database::{
+        connect_pool, set_mysql_read_only, set_mysql_statement_timeout, set_postgres_read_only,
+        set_postgres_statement_timeout, Databases,
+    },
+    dev,
+    error_reporting::ErrorReporting,
+    fixtures::Fixtures,
+    graphql::{root_schema_builder, SessionCache},
+    grpc::{proto::session_service_server::SessionServiceServer, SessionGrpc},
+    jwt::JwtValidator,
+    listener::{serve_unix, Listen},
+    log_level::LogLevel,
+    mock_opa::MockOpa,
+    opa::{
+        BreakerMode, CircuitBreaker, DecisionCache, ForwardClaims, OpaClient, OpaTls, PublicPolicy,
+        RetryPolicy, AUDIT_TARGET,
+    },
+    openapi,
+    operation_metrics::OperationMetrics,
+    operation_tracing::OperationTracing,
+    rate_limit::RateLimiter,
+    route_handlers::{is_probing, GraphQLHandler},
+    router::{setup_admin_router, setup_openapi_router, setup_router},
+    safelist::Safelist,
+    slow_resolvers::SlowResolvers,
+    startup_error::StartupError,
+    tls::TlsFiles,
+};
+use anyhow::Context;
+use async_graphql::{
+    extensions::apollo_persisted_queries::{ApolloPersistedQueries, LruCacheStorage},
+    SDLExportOptions,
+};
+use axum::{
+    http::{HeaderName, HeaderValue, Method},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use clap::{ArgGroup, Args, CommandFactory, FromArgMatches, Parser};
+use futures::FutureExt;
+use opentelemetry_otlp::WithExportConfig;
+use sea_orm::{
+    ConnectOptions, Database, DatabaseConnection, DbErr, RuntimeErr, SqlxMySqlConnector,
+    SqlxPostgresConnector, TransactionError,
+};
+use sqlx::{mysql::MySqlConnectOptions, postgres::PgConnectOptions, MySql, Postgres};
+use std::{
+    collections::HashSet,
+    fs::File,
+    future::{Future, IntoFuture},
+    io::Write,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    num::NonZeroU32,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use tonic::transport::server::TcpIncoming;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{info, instrument, warn};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+use url::Url;
+
+/// A service providing Beamline Session data from ISPyB
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about=None)]
+#[allow(clippy::large_enum_variant)]
+enum Cli {
+    /// Starts a webserver serving the GraphQL API
+    Serve(ServeArgs),
+    /// Produces the GraphQL schema
+    Schema(SchemaArgs),
+    /// Checks the health of a server running locally, exiting with a non-zero status if it is unhealthy
+    Healthcheck(HealthcheckArgs),
+    /// Inserts fixtures into the tables of an existing database, such as one created for testing
+    Seed(SeedArgs),
+    /// Inspects the configuration of the server
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Produces a completion script for the shell
+    Completions(CompletionsArgs),
+    /// Produces the man page
+    Manpage(ManpageArgs),
+}
+
+/// Subcommands inspecting the configuration of the server
+#[derive(Debug, clap::Subcommand)]
+enum ConfigCommand {
+    /// Prints the effective configuration, resolved from flags, environment variables and the configuration file as `serve`
+    /// would, as a YAML configuration file with secrets redacted
+    Print(ServeArgs),
+}
+
+/// Sizing and timeouts of the database connection pool, each defaulting to that of [`ConnectOptions`] if unset
+#[derive(Debug, Args)]
+struct DatabasePoolArgs {
+    /// The maximum number of connections held by the pool
+    #[arg(long, env = "DB_MAX_CONNECTIONS")]
+    db_max_connections: Option<u32>,
+    /// The minimum number of connections held by the pool
+    #[arg(long, env = "DB_MIN_CONNECTIONS")]
+    db_min_connections: Option<u32>,
+    /// The maximum time to wait whilst establishing a connection
+    #[arg(long, env = "DB_CONNECT_TIMEOUT", value_parser = humantime::parse_duration)]
+    db_connect_timeout: Option<Duration>,
+    /// The maximum time to wait whilst acquiring a connection from the pool
+    #[arg(long, env = "DB_ACQUIRE_TIMEOUT", value_parser = humantime::parse_duration)]
+    db_acquire_timeout: Option<Duration>,
+    /// The maximum time a connection may remain idle before it is closed
+    #[arg(long, env = "DB_IDLE_TIMEOUT", value_parser = humantime::parse_duration)]
+    db_idle_timeout: Option<Duration>,
+}
+
+impl DatabasePoolArgs {
+    /// Applies the pool sizing and timeouts which are set to the [`ConnectOptions`]
+    fn configure(&self, options: &mut ConnectOptions) {
+        if let Some(max_connections) = self.db_max_connections {
+            options.max_connections(max_connections);
+        }
+        if let Some(min_connections) = self.db_min_connections {
+            options.min_connections(min_connections);
+        }
+        if let Some(connect_timeout) = self.db_connect_timeout {
+            options.connect_timeout(connect_timeout);
+        }
+        if let Some(acquire_timeout) = self.db_acquire_timeout {
+            options.acquire_timeout(acquire_timeout);
+        }
+        if let Some(idle_timeout) = self.db_idle_timeout {
+            options.idle_timeout(idle_timeout);
+        }
+    }
+}
+
+/// The formats in which logs may be written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum LogFormat {
+    /// Human readable lines of text
+    Text,
+    /// A JSON object per line, with the fields of each event alongside its `timestamp`, `level`, `target` and `message`
+    Json,
+}
+
+/// The sampling of the traces sent to the OpenTelemetry collector
+#[derive(Debug, Args)]
+struct TraceSamplingArgs {
+    /// The sampler deciding which traces are recorded, named as per the OpenTelemetry specification
+    #[arg(long, env = "OTEL_TRACES_SAMPLER", value_enum, default_value_t = TraceSampler::ParentBasedAlwaysOn)]
+    trace_sampler: TraceSampler,
+    /// The fraction of traces recorded by the ratio based samplers, within `0.0..=1.0`
+    #[arg(long, env = "OTEL_TRACES_SAMPLER_ARG", default_value_t = 1.0, value_parser = parse_fraction)]
+    trace_sampler_ratio: f64,
+}
+
+impl TraceSamplingArgs {
+    /// The [`opentelemetry_sdk::trace::Sampler`] described by the arguments
+    fn sampler(&self) -> opentelemetry_sdk::trace::Sampler {
+        use opentelemetry_sdk::trace::Sampler;
+        match self.trace_sampler {
+            TraceSampler::AlwaysOn => Sampler::AlwaysOn,
+            TraceSampler::AlwaysOff => Sampler::AlwaysOff,
+            TraceSampler::TraceIdRatio => Sampler::TraceIdRatioBased(self.trace_sampler_ratio),
+            TraceSampler::ParentBasedAlwaysOn => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+            TraceSampler::ParentBasedAlwaysOff => {
+                Sampler::ParentBased(Box::new(Sampler::AlwaysOff))
+            }
+            TraceSampler::ParentBasedTraceIdRatio => Sampler::ParentBased(Box::new(
+                Sampler::TraceIdRatioBased(self.trace_sampler_ratio),
+            )),
+        }
+    }
+}
+
+/// The samplers by which traces may be selected for recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum TraceSampler {
+    /// Record every trace
+    #[value(name = "always_on")]
+    AlwaysOn,
+    /// Record no traces
+    #[value(name = "always_off")]
+    AlwaysOff,
+    /// Record a fraction of traces, chosen by their identifiers
+    #[value(name = "traceidratio")]
+    TraceIdRatio,
+    /// Follow the decision of the parent span, recording every root trace
+    #[value(name = "parentbased_always_on")]
+    ParentBasedAlwaysOn,
+    /// Follow the decision of the parent span, recording no root traces
+    #[value(name = "parentbased_always_off")]
+    ParentBasedAlwaysOff,
+    /// Follow the decision of the parent span, recording a fraction of root traces
+    #[value(name = "parentbased_traceidratio")]
+    ParentBasedTraceIdRatio,
+}
+
+/// Logging of the statements executed against the database
+#[derive(Debug, Args)]
+struct DatabaseLogArgs {
+    /// The level at which each database statement is logged
+    #[arg(long, env = "DB_LOG_LEVEL", default_value_t = tracing::log::LevelFilter::Debug)]
+    db_log_level: tracing::log::LevelFilter,
+    /// The execution time beyond which database statements are logged as warnings, along with their duration
+    #[arg(long, env = "DB_SLOW_STATEMENT_THRESHOLD", default_value = "1s", value_parser = humantime::parse_duration)]
+    db_slow_statement_threshold: Duration,
+}
+
+impl DatabaseLogArgs {
+    /// Applies the statement logging levels to the [`sqlx::ConnectOptions`]
+    fn configure<C: sqlx::ConnectOptions>(&self, options: C) -> C {
+        options
+            .log_statements(self.db_log_level)
+            .log_slow_statements(
+                tracing::log::LevelFilter::Warn,
+                self.db_slow_statement_threshold,
+            )
+    }
+}
+
+/// The Cross-Origin Resource Sharing policy, permitting browser applications on other origins to call the API
+#[derive(Debug, Args)]
+struct CorsArgs {
+    /// The origins from which cross-origin requests are permitted, or `*` for any origin, if unset none are permitted
+    #[arg(long, env = "CORS_ALLOWED_ORIGINS", value_delimiter = ',')]
+    cors_allowed_origins: Vec<HeaderValue>,
+    /// The methods permitted in cross-origin requests
+    #[arg(long, env = "CORS_ALLOWED_METHODS", value_delimiter = ',', default_values = ["GET", "POST"])]
+    cors_allowed_methods: Vec<Method>,
+    /// The headers permitted in cross-origin requests
+    #[arg(long, env = "CORS_ALLOWED_HEADERS", value_delimiter = ',', default_values = ["authorization", "content-type"])]
+    cors_allowed_headers: Vec<HeaderName>,
+    /// Permits cross-origin requests to include credentials, such as the token cookie
+    #[arg(long, env = "CORS_ALLOW_CREDENTIALS")]
+    cors_allow_credentials: bool,
+}
+
+impl CorsArgs {
+    /// Creates a [`CorsLayer`] enforcing the policy, if any origins are permitted
+    fn layer(&self) -> Result<Option<CorsLayer>, anyhow::Error> {
+        if self.cors_allowed_origins.is_empty() {
+            return Ok(None);
+        }
+        let origins = if self.cors_allowed_origins.iter().any(|origin| origin == "*") {
+            if self.cors_allow_credentials {
+                anyhow::bail!("Credentials cannot be permitted in requests from any origin");
+            }
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(self.cors_allowed_origins.clone())
+        };
+        Ok(Some(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods(self.cors_allowed_methods.clone())
+                .allow_headers(self.cors_allowed_headers.clone())
+                .allow_credentials(self.cors_allow_credentials),
+        ))
+    }
+}
+
+/// The rate limiting of GraphQL requests made by each client, identified by the subject of its validated token or otherwise by its address
+#[derive(Debug, Args)]
+struct RateLimitArgs {
+    /// The sustained number of requests per second permitted from each client, if unset requests are not limited
+    #[arg(long, env = "RATE_LIMIT")]
+    rate_limit: Option<NonZeroU32>,
+    /// The number of requests each client may make in a burst above the sustained rate
+    #[arg(long, env = "RATE_LIMIT_BURST", default_value = "10")]
+    rate_limit_burst: NonZeroU32,
+    /// Identifies clients without a token by the first address in the `X-Forwarded-For` header, which must be set by a
+    /// trusted proxy
+    #[arg(long, env = "RATE_LIMIT_TRUST_FORWARDED_FOR")]
+    rate_limit_trust_forwarded_for: bool,
+}
+
+impl RateLimitArgs {
+    /// Creates a [`RateLimiter`] enforcing the rate, if one is set, identifying clients by tokens validated by the
+    /// [`JwtValidator`]
+    fn limiter(&self, validator: Option<Arc<JwtValidator>>) -> Option<RateLimiter> {
+        self.rate_limit.map(|rate_limit| {
+            RateLimiter::new(
+                rate_limit,
+                self.rate_limit_burst,
+                self.rate_limit_trust_forwarded_for,
+            )
+            .with_jwt_validator(validator)
+        })
+    }
+}
+
+/// Arguments for serving the GraphQL API
+#[derive(Debug, Parser)]
+#[command(group = ArgGroup::new("mock_opa_permitted").args(["dev", "allow_mock_opa_with_database"]).multiple(true))]
+struct ServeArgs {
+    /// The path of a YAML file of argument values, each overridden by its environment variable or flag if set
+    #[arg(long, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+    /// The path of a dotenv file of environment variables, each overridden by the variable if already set, loaded in place of
+    /// `.env` in the working directory
+    #[arg(long, env = "ENV_FILE")]
+    env_file: Option<PathBuf>,
+    /// The port to which this application should bind
+    #[arg(short, long, env = "PORT", default_value_t = 80)]
+    port: u16,
+    /// The address on which to listen, either a socket address or `unix:` followed by the path of a Unix domain socket, in place of the port
+    #[arg(long, env = "LISTEN")]
+    listen: Option<Listen>,
+    /// The path at which the GraphQL endpoint and GraphiQL are served, such that the service may sit behind a shared ingress without path rewriting
+    #[arg(long, env = "ENDPOINT_PATH", default_value = "/", value_parser = parse_endpoint_path)]
+    endpoint_path: String,
+    /// The socket address, such as `127.0.0.1:9090`, on which the probes and metrics are served apart from the GraphQL API, if unset they are served alongside it
+    #[arg(long, env = "ADMIN_LISTEN")]
+    admin_listen: Option<SocketAddr>,
+    /// The socket address, such as `127.0.0.1:50051`, on which the gRPC API is served, if unset it is not served
+    #[arg(long, env = "GRPC_LISTEN")]
+    grpc_listen: Option<SocketAddr>,
+    /// The path of a PEM encoded TLS certificate chain, with which HTTPS is served in place of HTTP
+    #[arg(long, env = "TLS_CERT", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// The path of the PEM encoded private key of the TLS certificate
+    #[arg(long, env = "TLS_KEY", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// The interval at which the TLS certificate and private key are checked for changes and reloaded
+    #[arg(long, env = "TLS_RELOAD_INTERVAL", default_value = "1m", value_parser = humantime::parse_duration)]
+    tls_reload_interval: Duration,
+    /// The Cross-Origin Resource Sharing policy
+    #[command(flatten)]
+    cors: CorsArgs,
+    /// The maximum size, in bytes, of the body of a GraphQL request
+    #[arg(long, env = "MAX_BODY_SIZE", default_value_t = 1024 * 1024)]
+    max_body_size: usize,
+    /// The maximum time taken to read, execute and respond to a GraphQL request, after which it is cancelled
+    #[arg(long, env = "REQUEST_TIMEOUT", default_value = "30s", value_parser = humantime::parse_duration)]
+    request_timeout: Duration,
+    /// The maximum number of GraphQL requests in flight, beyond which requests are shed with service unavailable, if unset requests are not limited
+    #[arg(long, env = "MAX_CONCURRENT_REQUESTS")]
+    max_concurrent_requests: Option<usize>,
+    /// The maximum number of operations in a batched GraphQL request
+    #[arg(long, env = "MAX_BATCH_SIZE", default_value_t = 20)]
+    max_batch_size: usize,
+    /// The maximum age for which responses to a named operation may be cached, as `NAME=DURATION`, in place of that of the schema
+    #[arg(long = "operation-max-age", env = "OPERATION_MAX_AGES", value_delimiter = ',', value_parser = parse_operation_max_age)]
+    operation_max_ages: Vec<(String, Duration)>,
+    /// The rate limiting of GraphQL requests made by each client
+    #[command(flatten)]
+    rate_limit: RateLimitArgs,
+    /// Serves seeded data from an in-memory SQLite database in place of ISPyB, authorizing with a permissive
+    /// mock of the Open Policy Agent unless its URL is provided
+    #[arg(long, env = "DEV_MODE")]
+    dev: bool,
+    /// A YAML, or JSON, file of fixtures seeded into the development database in place of the representative data
+    #[arg(long, env = "DEV_FIXTURES", requires = "dev")]
+    dev_fixtures: Option<PathBuf>,
+    /// The URL of the ISPyB instance which should be connected to, either MySQL or Postgres
+    #[arg(long, env = "DATABASE_URL", required_unless_present = "dev")]
+    database_url: Option<Url>,
+    /// The URLs of read replicas of the ISPyB instance, between which read-only queries are balanced
+    #[arg(long, env = "DATABASE_REPLICA_URLS", value_delimiter = ',')]
+    database_replica_urls: Vec<Url>,
+    /// Sizing and timeouts of the database connection pool
+    #[command(flatten)]
+    database_pool: DatabasePoolArgs,
+    /// Logging of the statements executed against the database
+    #[command(flatten)]
+    database_log: DatabaseLogArgs,
+    /// The maximum number of attempts made for each database query
+    #[arg(long, env = "DB_RETRY_ATTEMPTS", default_value_t = 3)]
+    db_retry_attempts: u32,
+    /// The delay before retrying a failed database query, doubled on each subsequent retry
+    #[arg(long, env = "DB_RETRY_BACKOFF", default_value = "100ms", value_parser = humantime::parse_duration)]
+    db_retry_backoff: Duration,
+    /// The fraction of each database retry delay which is randomised
+    #[arg(long, env = "DB_RETRY_JITTER", default_value_t = 0.5, value_parser = parse_fraction)]
+    db_retry_jitter: f64,
+    /// The maximum execution time of each database statement, after which it is aborted
+    #[arg(long, env = "DB_STATEMENT_TIMEOUT", value_parser = humantime::parse_duration)]
+    db_statement_timeout: Option<Duration>,
+    /// Restricts database sessions to read-only transactions and refuses to start unless the database user holds no
+    /// write grants
+    #[arg(long, env = "DB_READ_ONLY")]
+    db_read_only: bool,
+    /// Makes all database queries of each GraphQL operation within a single read-only `REPEATABLE READ`
+    /// transaction, such that the fields of the response are mutually consistent
+    #[arg(long, env = "DB_SNAPSHOT_READS")]
+    db_snapshot_reads: bool,
+    /// Serves without first checking that the database has the tables and columns of the queried entities, which is otherwise
+    /// fatal if they are missing
+    #[arg(long, env = "DB_SKIP_SCHEMA_CHECK")]
+    db_skip_schema_check: bool,
+    /// The maximum time spent retrying the initial database connection, after which connections are established lazily
+    #[arg(long, env = "DB_STARTUP_TIMEOUT", default_value = "30s", value_parser = humantime::parse_duration)]
+    db_startup_timeout: Duration,
+    /// The URL of a Redis instance in which cached values are shared between replicas, if unset values are cached in memory
+    #[arg(long, env = "CACHE_URL")]
+    cache_url: Option<Url>,
+    /// How long the results of session lookups are cached for, if unset results are not cached
+    #[arg(long, env = "SESSION_CACHE_TTL", value_parser = humantime::parse_duration)]
+    session_cache_ttl: Option<Duration>,
+    /// The maximum number of session lookup results cached in memory
+    #[arg(long, env = "SESSION_CACHE_CAPACITY", default_value_t = 1_000)]
+    session_cache_capacity: u64,
+    /// The maximum number of Automatic Persisted Queries registered, the least recently used being evicted first, unless restricted to a manifest
+    #[arg(long, env = "PERSISTED_QUERY_CAPACITY", default_value_t = 1_000)]
+    persisted_query_capacity: usize,
+    /// A JSON persisted query manifest, listing the only operations which may be executed besides those of the REST and gRPC APIs, if unset arbitrary operations are permitted
+    #[arg(long, env = "PERSISTED_QUERY_MANIFEST")]
+    persisted_query_manifest: Option<PathBuf>,
+    /// Whether to stop serving the GraphiQL IDE, such that GET requests without a query are answered with not found
+    #[arg(long, env = "DISABLE_GRAPHIQL")]
+    disable_graphiql: bool,
+    /// Whether to reject introspection queries, such that the schema is not revealed to clients
+    #[arg(long, env = "DISABLE_INTROSPECTION")]
+    disable_introspection: bool,
+    /// The URL of the Open Policy Agent instance used for authorization
+    #[arg(long, env = "OPA_URL", required_unless_present_any = ["dev", "mock_opa"])]
+    opa_url: Option<Url>,
+    /// Authorizes with a mock of the Open Policy Agent, permitting every operation unless denied by the rules in
+    /// MOCK_OPA_RULES, in place of that at OPA_URL, only in development mode unless ALLOW_MOCK_OPA_WITH_DATABASE is set
+    #[arg(
+        long,
+        env = "MOCK_OPA",
+        conflicts_with = "opa_url",
+        requires = "mock_opa_permitted"
+    )]
+    mock_opa: bool,
+    /// Permits the mock of the Open Policy Agent to authorize access to the database at DATABASE_URL, such as one seeded
+    /// for integration tests, which would otherwise permit every operation on real data
+    #[arg(long, requires = "mock_opa")]
+    allow_mock_opa_with_database: bool,
+    /// A YAML file listing the rules of the mock Open Policy Agent, each of which permits or denies the inputs containing
+    /// its `input`, optionally only of its `policy`, by whether to `allow` them and a `reason`
+    #[arg(long, env = "MOCK_OPA_RULES", requires = "mock_opa")]
+    mock_opa_rules: Option<PathBuf>,
+    /// The maximum time to wait for a response from the Open Policy Agent
+    #[arg(long, env = "OPA_TIMEOUT", default_value = "5s", value_parser = humantime::parse_duration)]
+    opa_timeout: Duration,
+    /// The maximum number of attempts made for each request to the Open Policy Agent
+    #[arg(long, env = "OPA_RETRY_ATTEMPTS", default_value_t = 3)]
+    opa_retry_attempts: u32,
+    /// The delay before retrying a failed request to the Open Policy Agent, doubled on each subsequent retry
+    #[arg(long, env = "OPA_RETRY_BACKOFF", default_value = "100ms", value_parser = humantime::parse_duration)]
+    opa_retry_backoff: Duration,
+    /// The fraction of each Open Policy Agent retry delay which is randomised
+    #[arg(long, env = "OPA_RETRY_JITTER", default_value_t = 0.5, value_parser = parse_fraction)]
+    opa_retry_jitter: f64,
+    /// The number of consecutive failed requests to the Open Policy Agent after which requests are suspended
+    #[arg(long, env = "OPA_BREAKER_THRESHOLD", default_value_t = 5)]
+    opa_breaker_threshold: u32,
+    /// How long requests to the Open Policy Agent are suspended before a trial request is made
+    #[arg(long, env = "OPA_BREAKER_COOLDOWN", default_value = "30s", value_parser = humantime::parse_duration)]
+    opa_breaker_cooldown: Duration,
+    /// How policy decisions are made whilst requests to the Open Policy Agent are suspended
+    #[arg(long, env = "OPA_BREAKER_MODE", value_enum, default_value_t = BreakerMode::Deny)]
+    opa_breaker_mode: BreakerMode,
+    /// The path of a PEM encoded client certificate chain presented to the Open Policy Agent
+    #[arg(long, env = "OPA_CLIENT_CERT", requires = "opa_client_key")]
+    opa_client_cert: Option<PathBuf>,
+    /// The path of the PEM encoded private key of the Open Policy Agent client certificate
+    #[arg(long, env = "OPA_CLIENT_KEY", requires = "opa_client_cert")]
+    opa_client_key: Option<PathBuf>,
+    /// The path of a PEM encoded bundle of certificate authorities trusted to sign the Open Policy Agent server certificate
+    #[arg(long, env = "OPA_CA_BUNDLE")]
+    opa_ca_bundle: Option<PathBuf>,
+    /// The path of the Open Policy Agent policy authorizing access to non-sensitive metadata, `public/anonymous` permits unauthenticated access
+    #[arg(long, env = "OPA_PUBLIC_POLICY", default_value = "public/read")]
+    opa_public_policy: String,
+    /// The path of an Open Policy Agent policy document which must be loaded for the service to be ready
+    #[arg(long, env = "OPA_REQUIRED_POLICY", default_value = "system/main")]
+    opa_required_policy: String,
+    /// How long Open Policy Agent decisions are cached for, if unset decisions are not cached
+    #[arg(long, env = "OPA_DECISION_CACHE_TTL", value_parser = humantime::parse_duration)]
+    opa_decision_cache_ttl: Option<Duration>,
+    /// The maximum number of Open Policy Agent decisions cached in memory
+    #[arg(long, env = "OPA_DECISION_CACHE_CAPACITY", default_value_t = 10_000)]
+    opa_decision_cache_capacity: u64,
+    /// How often Open Policy Agent bundle revisions are polled, clearing the decision cache when they change
+    #[arg(long, env = "OPA_REVISION_POLL_INTERVAL", default_value = "10s", value_parser = humantime::parse_duration)]
+    opa_revision_poll_interval: Duration,
+    /// The URL of the JSON Web Key Set used to validate bearer tokens, if unset tokens are passed to the Open Policy Agent unvalidated
+    #[arg(long, env = "JWKS_URL")]
+    jwks_url: Option<Url>,
+    /// The issuer required of bearer tokens
+    #[arg(long, env = "JWT_ISSUER", requires = "jwks_url")]
+    jwt_issuer: Option<String>,
+    /// The audiences, any of which is required of bearer tokens
+    #[arg(
+        long,
+        env = "JWT_AUDIENCE",
+        value_delimiter = ',',
+        requires = "jwks_url"
+    )]
+    jwt_audience: Vec<String>,
+    /// Send the claims of validated bearer tokens to the Open Policy Agent in place of the tokens themselves
+    #[arg(long, env = "OPA_FORWARD_CLAIMS", requires = "jwks_url")]
+    opa_forward_claims: bool,
+    /// The name of a cookie from which the bearer token is read when no Authorization header is present
+    #[arg(long, env = "TOKEN_COOKIE")]
+    token_cookie: Option<String>,
+    /// The path of a JSON file listing the `identity` and `key_sha256` of each accepted API key
+    #[arg(long, env = "API_KEYS_FILE")]
+    api_keys_file: Option<PathBuf>,
+    /// The header from which API keys are read
+    #[arg(long, env = "API_KEY_HEADER", default_value = "x-api-key")]
+    api_key_header: HeaderName,
+    /// The header from which client credentials service tokens, identifying a gateway acting on behalf of the user, are read
+    #[arg(long, env = "SERVICE_TOKEN_HEADER", requires = "jwks_url")]
+    service_token_header: Option<HeaderName>,
+    /// The [`tracing::Level`] to log at, which is toggled to [`tracing::Level::DEBUG`] and back on receipt of a hangup signal,
+    /// and may be set at the `/admin/log-level` endpoint when the admin listener is configured
+    #[arg(long, env = "LOG_LEVEL", default_value_t = tracing::Level::INFO)]
+    log_level: tracing::Level,
+    /// The duration beyond which the execution of a resolver is logged as a warning, if unset resolvers are not timed
+    #[arg(long, env = "SLOW_RESOLVER_THRESHOLD", value_parser = humantime::parse_duration)]
+    slow_resolver_threshold: Option<Duration>,
+    /// The format in which logs are written to stdout
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// The URL of the OpenTelemetry collector to send traces to
+    #[arg(long, env = "OTEL_COLLECTOR_URL")]
+    otel_collector_url: Option<Url>,
+    /// The environment, such as `production` or `staging`, the service is deployed to, attached to traces and metrics
+    #[arg(long, env = "DEPLOYMENT_ENVIRONMENT")]
+    deployment_environment: Option<String>,
+    /// The Data Source Name of the Sentry project to which panics and unexpected errors are reported
+    #[arg(long, env = "SENTRY_DSN")]
+    sentry_dsn: Option<sentry::types::Dsn>,
+    /// The sampling of the traces sent to the OpenTelemetry collector
+    #[command(flatten)]
+    trace_sampling: TraceSamplingArgs,
+    /// Serves metrics for scraping by Prometheus, in addition to sending them to any OpenTelemetry collector
+    #[arg(long, env = "PROMETHEUS_METRICS")]
+    prometheus_metrics: bool,
+    /// The path of a file to which authorization audit records are appended
+    #[arg(long, env = "AUDIT_LOG")]
+    audit_log: Option<PathBuf>,
+    /// The maximum time to wait for in-flight requests to complete when shutting down
+    #[arg(long, env = "SHUTDOWN_TIMEOUT", default_value = "30s", value_parser = humantime::parse_duration)]
+    shutdown_timeout: Duration,
+    /// Loads the configuration and checks that the database schema and Open Policy Agent policy are as required, then
+    /// exits in place of serving
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Arguments for produces the GraphQL schema
+#[derive(Debug, Parser)]
+struct SchemaArgs {
+    /// The path to write the schema to, if not set the schema will be printed to stdout
+    #[arg(short, long)]
+    path: Option<PathBuf>,
+    /// Omits the Apollo Federation directives, such as `@key`, for tooling which does not support them
+    #[arg(long)]
+    no_federation: bool,
+    /// Sorts fields, arguments and enum values alphabetically, rather than in declaration order
+    #[arg(long)]
+    sorted: bool,
+    /// Writes short descriptions on a single line, rather than as block strings
+    #[arg(long)]
+    single_line_descriptions: bool,
+    /// Includes the `@specifiedBy` directive of custom scalars
+    #[arg(long)]
+    include_specified_by: bool,
+}
+
+/// Arguments for producing a completion script
+#[derive(Debug, Parser)]
+struct CompletionsArgs {
+    /// The shell to complete commands in
+    shell: clap_complete::Shell,
+}
+
+/// Arguments for producing the man page
+#[derive(Debug, Parser)]
+struct ManpageArgs {
+    /// The path to write the man page to, if not set the man page will be printed to stdout
+    #[arg(short, long)]
+    path: Option<PathBuf>,
+}
+
+impl SchemaArgs {
+    /// The [`SDLExportOptions`] selected by the arguments
+    fn sdl_options(&self) -> SDLExportOptions {
+        let mut options = SDLExportOptions::new();
+        if !self.no_federation {
+            options = options.federation();
+        }
+        if self.sorted {
+            options = options
+                .sorted_fields()
+                .sorted_arguments()
+                .sorted_enum_items();
+        }
+        if self.single_line_descriptions {
+            options = options.prefer_single_line_descriptions();
+        }
+        if self.include_specified_by {
+            options = options.include_specified_by();
+        }
+        options
+    }
+}
+
+/// Arguments for checking the health of a server running locally
+#[derive(Debug, Parser)]
+struct HealthcheckArgs {
+    /// The port on which the server is listening
+    #[arg(short, long, env = "PORT", default_value_t = 80)]
+    port: u16,
+    /// The socket address on which the server serves its probes apart from the GraphQL API, if any
+    #[arg(long, env = "ADMIN_LISTEN")]
+    admin_listen: Option<SocketAddr>,
+    /// The path of the TLS certificate chain of the server, if set the probe is made over HTTPS
+    #[arg(long, env = "TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+    /// Checks that the server is ready to handle requests, rather than only alive
+    #[arg(long)]
+    ready: bool,
+    /// The maximum time to wait for a response
+    #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+    timeout: Duration,
+}
+
+impl HealthcheckArgs {
+    /// The URL of the liveness or readiness probe of the server
+    fn url(&self) -> String {
+        let path = if self.ready { "readyz" } else { "healthz" };
+        match self.admin_listen {
+            Some(admin_listen) if admin_listen.ip().is_unspecified() => {
+                format!("http://localhost:{}/{path}", admin_listen.port())
+            }
+            Some(admin_listen) => format!("http://{admin_listen}/{path}"),
+            None if self.tls_cert.is_some() => format!("https://localhost:{}/{path}", self.port),
+            None => format!("http://localhost:{}/{path}", self.port),
+        }
+    }
+}
+
+/// Arguments for seeding a database with fixtures
+#[derive(Debug, Parser)]
+struct SeedArgs {
+    /// The URL of the database into which the fixtures are inserted, either MySQL, Postgres or SQLite
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: Url,
+    /// The YAML, or JSON, files of fixtures, inserted in the order given
+    #[arg(required = true)]
+    fixtures: Vec<PathBuf>,
+}
+
+/// Inserts the fixtures of each file into the database, in the order given
+async fn seed(args: SeedArgs) -> Result<(), StartupError> {
+    let fixtures = args
+        .fixtures
+        .iter()
+        .map(|path| Fixtures::load(path).with_context(|| format!("fixtures {}", path.display())))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(StartupError::Config)?;
+    let connection = Database::connect(args.database_url.as_str())
+        .await
+        .map_err(|err| StartupError::Database(err.into()))?;
+    for fixtures in fixtures {
+        fixtures
+            .insert(&connection)
+            .await
+            .map_err(|err| StartupError::Database(err.into()))?;
+        println!("Seeded {fixtures}");
+    }
+    Ok(())
+}
+
+/// Requests the liveness or readiness probe of the server, failing if it cannot be reached or does not respond successfully
+///
+/// The certificate of the server is not verified, as it is unlikely to be issued for `localhost`
+async fn healthcheck(args: HealthcheckArgs) -> Result<(), anyhow::Error> {
+    reqwest::Client::builder()
+        .timeout(args.timeout)
+        .danger_accept_invalid_certs(true)
+        .build()?
+        .get(args.url())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Parses an operation name and the maximum age for which its responses may be cached, separated by `=`
+fn parse_operation_max_age(value: &str) -> Result<(String, Duration), String> {
+    let (operation, max_age) = value
+        .split_once('=')
+        .ok_or(format!("{value} is not of the form NAME=DURATION"))?;
+    let max_age = humantime::parse_duration(max_age).map_err(|err| err.to_string())?;
+    Ok((operation.to_string(), max_age))
+}
+
+/// Parses a fraction, within `0.0..=1.0`
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction = value.parse::<f64>().map_err(|err| err.to_string())?;
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!("{value} is not within 0.0..=1.0"));
+    }
+    Ok(fraction)
+}
+
+/// Parses an absolute URI path, without any trailing slash unless it is the root
+fn parse_endpoint_path(path: &str) -> Result<String, String> {
+    if !path.starts_with('/') {
+        return Err(format!("{path} is not an absolute path"));
+    }
+    match path.trim_end_matches('/') {
+        "" => Ok("/".to_string()),
+        path => Ok(path.to_string()),
+    }
+}
+
+/// The prefix by which environment variables may be namespaced, such that they do not collide with those of other services
+/// sharing an environment file
+const ENV_PREFIX: &str = "GRAPH_SESSIONS_";
+
+/// The command line interface, each argument of which reads its [`ENV_PREFIX`]ed environment variable in place of the
+/// bare one if it is set, such that prefixed variables take precedence over bare ones without modifying the environment
+///
+/// Environment variables are read as the command is built, so it is rebuilt after each change to them
+fn command() -> clap::Command {
+    with_env_prefix(Cli::command())
+}
+
+/// Reads the [`ENV_PREFIX`]ed environment variable of each argument of the command and its subcommands, where set
+fn with_env_prefix(mut command: clap::Command) -> clap::Command {
+    let prefixed = command
+        .get_arguments()
+        .filter_map(|argument| {
+            let mut prefixed = std::ffi::OsString::from(ENV_PREFIX);
+            prefixed.push(argument.get_env()?);
+            std::env::var_os(&prefixed).map(|_| (argument.get_id().clone(), prefixed))
+        })
+        .collect::<Vec<_>>();
+    for (id, prefixed) in prefixed {
+        command = command.mut_arg(id, |argument| argument.env(prefixed));
+    }
+    let subcommands = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect::<Vec<_>>();
+    for subcommand in subcommands {
+        command = command.mut_subcommand(subcommand, with_env_prefix);
+    }
+    command
+}
+
+/// Runs the future to completion on a multi-threaded [`tokio`] runtime
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap_or_else(|err| StartupError::Serve(err).exit())
+        .block_on(future)
+}
+
+/// The matches of the arguments of the server, whether it is to be served or its configuration printed
+fn serve_matches(matches: &clap::ArgMatches) -> Option<&clap::ArgMatches> {
+    match matches.subcommand() {
+        Some(("serve", matches)) => Some(matches),
+        Some(("config", matches)) => match matches.subcommand() {
+            Some(("print", matches)) => Some(matches),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses the command line and runs the selected subcommand, exiting the process if it fails
+///
+/// The command line, environment file and configuration file are resolved before the asynchronous runtime is started,
+/// whilst the process has a single thread and so may safely modify its environment
+pub fn run() {
+    let env_file = serve_matches(&command().ignore_errors(true).get_matches())
+        .and_then(|matches| matches.get_one::<PathBuf>("env_file").cloned());
+    match env_file {
+        Some(path) => {
+            if let Err(err) = dotenvy::from_path(&path) {
+                StartupError::Config(anyhow::anyhow!(
+                    "environment file {}: {err}",
+                    path.display()
+                ))
+                .exit();
+            }
+        }
+        None => {
+            dotenvy::dotenv().ok();
+        }
+    }
+    let cli = command();
+    let serve_command = cli.find_subcommand("serve").unwrap();
+    let preliminary = cli.clone().ignore_errors(true).get_matches();
+    let config = serve_matches(&preliminary)
+        .and_then(|matches| Some((matches, matches.get_one::<PathBuf>("config")?.clone())));
+    let (flags, loaded) = match config {
+        Some((matches, path)) => {
+            config_file::load(&path, serve_command, matches).unwrap_or_else(|err| {
+                StartupError::Config(err.context(format!("configuration file {}", path.display())))
+                    .exit()
+            })
+        }
+        None => (Vec::new(), HashSet::new()),
+    };
+    let matches = cli
+        .clone()
+        .get_matches_from(std::env::args_os().chain(flags));
+    let args = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    match args {
+        Cli::Serve(args) => {
+            if let Err(err) = block_on(serve_api(args)) {
+                err.exit();
+            }
+        }
+        Cli::Schema(args) => {
+            let schema = root_schema_builder().finish();
+            let schema_string = schema.sdl_with_options(args.sdl_options());
+            if let Some(path) = args.path {
+                File::create(&path)
+                    .and_then(|mut file| file.write_all(schema_string.as_bytes()))
+                    .with_context(|| format!("schema file {}", path.display()))
+                    .unwrap_or_else(|err| StartupError::Config(err).exit());
+            } else {
+                println!("{}", schema_string)
+            }
+        }
+        Cli::Completions(args) => {
+            clap_complete::generate(
+                args.shell,
+                &mut Cli::command(),
+                built_info::PKG_NAME,
+                &mut std::io::stdout(),
+            );
+        }
+        Cli::Manpage(args) => {
+            let man = clap_mangen::Man::new(Cli::command());
+            match args.path {
+                Some(path) => File::create(&path)
+                    .and_then(|mut file| man.render(&mut file))
+                    .with_context(|| format!("manual page {}", path.display())),
+                None => man
+                    .render(&mut std::io::stdout())
+                    .context("manual page to standard output"),
+            }
+            .unwrap_or_else(|err| StartupError::Config(err).exit());
+        }
+        Cli::Seed(args) => {
+            if let Err(err) = block_on(seed(args)) {
+                err.exit();
+            }
+        }
+        Cli::Healthcheck(args) => {
+            if let Err(err) = block_on(healthcheck(args)) {
+                eprintln!("Unhealthy: {err}");
+                std::process::exit(1);
+            }
+        }
+        Cli::Config(ConfigCommand::Print(_)) => {
+            print!(
+                "{}",
+                config_file::render(serve_command, serve_matches(&matches).unwrap(), &loaded)
+            );
+        }
+    }
+}
+
+/// Starts the server, serving the GraphQL API until a shutdown signal is received
+async fn serve_api(args: ServeArgs) -> Result<(), StartupError> {
+    let error_reporting =
+        setup_error_reporting(args.sentry_dsn, args.deployment_environment.clone());
+    let telemetry = setup_telemetry(
+        args.log_level,
+        args.log_format,
+        args.otel_collector_url,
+        error_reporting.is_some(),
+        args.deployment_environment,
+        args.trace_sampling.sampler(),
+        args.prometheus_metrics,
+        args.audit_log,
+    )
+    .map_err(StartupError::Telemetry)?;
+    println!(
+        "Starting {} {} ({}, {} build with {})",
+        built_info::PKG_NAME,
+        built_info::PKG_VERSION,
+        built_info::GIT_COMMIT,
+        built_info::PROFILE,
+        built_info::RUSTC_VERSION
+    );
+    built_info::export_build_info();
+    let cors = args.cors.layer().map_err(StartupError::Config)?;
+    let db_retry = RetryPolicy {
+        attempts: args.db_retry_attempts,
+        backoff: args.db_retry_backoff,
+        jitter: args.db_retry_jitter,
+    };
+    let database = match args.database_url {
+        Some(database_url) if !args.dev => {
+            let primary = setup_database(
+                database_url,
+                &args.database_pool,
+                &args.database_log,
+                args.db_statement_timeout,
+                args.db_read_only,
+                args.db_startup_timeout,
+                &db_retry,
+            )
+            .await
+            .map_err(|err| StartupError::Database(err.into()))?;
+            let mut replicas = Vec::with_capacity(args.database_replica_urls.len());
+            for replica_url in args.database_replica_urls {
+                replicas.push(
+                    setup_database(
+                        replica_url,
+                        &args.database_pool,
+                        &args.database_log,
+                        args.db_statement_timeout,
+                        args.db_read_only,
+                        args.db_startup_timeout,
+                        &db_retry,
+                    )
+                    .await
+                    .map_err(|err| StartupError::Database(err.into()))?,
+                );
+            }
+            let database = Databases::new(primary, replicas, db_retry);
+            if args.db_read_only {
+                database
+                    .check_write_grants()
+                    .await
+                    .map_err(|err| StartupError::Database(err.into()))?;
+            }
+            database
+        }
+        _ => Databases::new(
+            dev::seed_database(&match &args.dev_fixtures {
+                Some(path) => Fixtures::load(path)
+                    .with_context(|| format!("development fixtures {}", path.display()))
+                    .map_err(StartupError::Config)?,
+                None => Fixtures::development(),
+            })
+            .await
+            .map_err(|err| StartupError::Database(err.into()))?,
+            Vec::new(),
+            db_retry,
+        ),
+    };
+    if !args.db_skip_schema_check && !args.dry_run {
+        database
+            .check_schema()
+            .await
+            .map_err(|err| StartupError::Schema(err.into()))?;
+    }
+    database.export_pool_metrics();
+    #[cfg(tokio_unstable)]
+    crate::runtime_metrics::export_runtime_metrics(tokio::runtime::Handle::current());
+    #[cfg(not(tokio_unstable))]
+    info!("Runtime metrics are disabled, as the server was not compiled with --cfg tokio_unstable");
+    let redis = match &args.cache_url {
+        Some(cache_url) => RedisCache::connect(cache_url)
+            .await
+            .inspect_err(|err| warn!("Could not connect to Redis, caching in memory: {err}"))
+            .ok(),
+        None => None,
+    };
+    let cache = |namespace: &str, ttl: Duration, capacity: u64| match &redis {
+        Some(connection) => Cache::new(RedisCache::new(connection.clone(), namespace, ttl)),
+        None => Cache::new(MemoryCache::new(ttl, capacity)),
+    };
+    let opa_url = match args.opa_url {
+        Some(opa_url) => opa_url,
+        None => {
+            let mock = match &args.mock_opa_rules {
+                Some(path) => MockOpa::load(path)
+                    .with_context(|| format!("mock Open Policy Agent rules {}", path.display()))
+                    .map_err(StartupError::Config)?,
+                None => MockOpa::default(),
+            };
+            if args.mock_opa {
+                warn!("Authorizing with a mock Open Policy Agent, which does not enforce the deployed policies");
+            }
+            mock.serve()
+                .await
+                .context("mock Open Policy Agent")
+                .map_err(StartupError::Bind)?
+        }
+    };
+    let opa_client = OpaClient::new(
+        opa_url,
+        args.opa_timeout,
+        RetryPolicy {
+            attempts: args.opa_retry_attempts,
+            backoff: args.opa_retry_backoff,
+            jitter: args.opa_retry_jitter,
+        },
+        CircuitBreaker::new(
+            args.opa_breaker_threshold,
+            args.opa_breaker_cooldown,
+            args.opa_breaker_mode,
+        ),
+        &OpaTls {
+            client_identity: args.opa_client_cert.zip(args.opa_client_key),
+            ca_bundle: args.opa_ca_bundle,
+        },
+        args.opa_required_policy,
+        args.opa_decision_cache_ttl.map(|ttl| {
+            DecisionCache::new(cache("decision", ttl, args.opa_decision_cache_capacity))
+        }),
+    )
+    .context("Open Policy Agent client")
+    .map_err(StartupError::Tls)?;
+    tokio::spawn(
+        opa_client
+            .clone()
+            .watch_revisions(args.opa_revision_poll_interval),
+    );
+    match opa_client.ready().await {
+        Ok(()) => info!("Open Policy Agent is ready"),
+        Err(err) => warn!("Open Policy Agent is not ready: {err}"),
+    }
+    let schema_builder = root_schema_builder()
+        .data(database.clone())
+        .data(opa_client.clone())
+        .data(PublicPolicy(args.opa_public_policy))
+        .data(ForwardClaims(args.opa_forward_claims))
+        .data(
+            args.session_cache_ttl
+                .map(|ttl| SessionCache(cache("session", ttl, args.session_cache_capacity))),
+        );
+    let schema_builder = schema_builder
+        .extension(OperationMetrics)
+        .extension(OperationTracing)
+        .extension(ErrorReporting);
+    let schema_builder = match args.slow_resolver_threshold {
+        Some(threshold) => schema_builder.extension(SlowResolvers::new(threshold)),
+        None => schema_builder,
+    };
+    let schema_builder = if args.disable_introspection {
+        schema_builder.disable_introspection()
+    } else {
+        schema_builder
+    };
+    let schema = match args.persisted_query_manifest {
+        Some(path) => schema_builder.extension(
+            Safelist::load(&path)
+                .with_context(|| format!("persisted query manifest {}", path.display()))
+                .map_err(StartupError::Config)?,
+        ),
+        None => schema_builder.extension(ApolloPersistedQueries::new(LruCacheStorage::new(
+            args.persisted_query_capacity,
+        ))),
+    }
+    .finish();
+    let jwt_validator = args.jwks_url.map(|jwks_url| {
+        Arc::new(JwtValidator::new(
+            jwks_url,
+            args.jwt_issuer,
+            args.jwt_audience,
+        ))
+    });
+    let api_keys = args
+        .api_keys_file
+        .map(|path| {
+            ApiKeys::load(&path)
+                .with_context(|| format!("API keys file {}", path.display()))
+                .map(Arc::new)
+        })
+        .transpose()
+        .map_err(StartupError::Config)?;
+    if args.dry_run {
+        preflight(&database, &opa_client).await?;
+        println!("Preflight checks passed");
+        return Ok(());
+    }
+    let openapi = openapi::document(
+        &args.endpoint_path,
+        args.api_key_header.as_str(),
+        args.admin_listen.is_none(),
+    );
+    let handler = GraphQLHandler::new(schema)
+        .with_jwt_validator(jwt_validator.clone())
+        .with_token_cookie(args.token_cookie)
+        .with_api_keys(api_keys, args.api_key_header)
+        .with_service_token_header(args.service_token_header)
+        .with_snapshots(args.db_snapshot_reads.then(|| database.clone()))
+        .with_max_batch_size(args.max_batch_size)
+        .with_operation_max_ages(args.operation_max_ages.into_iter().collect());
+    let grpc_server = args
+        .grpc_listen
+        .map(|grpc_listen| (grpc_listen, SessionGrpc::server(handler.clone())));
+    let router = setup_router(
+        handler,
+        &args.endpoint_path,
+        cors,
+        !args.disable_graphiql,
+        args.max_body_size,
+        args.request_timeout,
+        args.max_concurrent_requests,
+        args.rate_limit.limiter(jwt_validator),
+    );
+    #[cfg(unix)]
+    tokio::spawn(telemetry.log_level.clone().toggle_on_hangup());
+    let admin_router = setup_admin_router(
+        opa_client,
+        database,
+        telemetry.prometheus_registry.clone(),
+        args.admin_listen
+            .is_some()
+            .then(|| telemetry.log_level.clone()),
+    );
+    let router = match args.admin_listen {
+        Some(admin_listen) => {
+            let listener = TcpListener::bind(admin_listen)
+                .await
+                .with_context(|| format!("admin listener {admin_listen}"))
+                .map_err(StartupError::Bind)?;
+            println!("Serving probes & metrics at {}", admin_listen);
+            tokio::spawn(serve_admin(admin_router, listener));
+            router
+        }
+        None => router.merge(admin_router),
+    };
+    let router = router.merge(setup_openapi_router(openapi));
+    if let Some((grpc_listen, grpc_server)) = grpc_server {
+        let listener = TcpListener::bind(grpc_listen)
+            .await
+            .with_context(|| format!("gRPC listener {grpc_listen}"))
+            .map_err(StartupError::Bind)?;
+        println!("Serving gRPC API at {}", grpc_listen);
+        tokio::spawn(serve_grpc(grpc_server, listener));
+    }
+    let tls = match (args.tls_cert, args.tls_key) {
+        (Some(cert), Some(key)) => {
+            let files = TlsFiles::new(cert, key);
+            let config = files
+                .load()
+                .await
+                .context("server certificate chain and private key")
+                .map_err(StartupError::Tls)?;
+            tokio::spawn(files.watch(config.clone(), args.tls_reload_interval));
+            Some(config)
+        }
+        _ => None,
+    };
+    let listen = args
+        .listen
+        .unwrap_or(Listen::Tcp(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::UNSPECIFIED,
+            args.port,
+        ))));
+    serve(router, listen, tls, args.shutdown_timeout)
+        .await
+        .map_err(StartupError::Serve)?;
+    telemetry.shutdown().await;
+    Ok(())
+}
+
+/// Checks that the database is reachable and has the tables and columns queried, and that the Open Policy Agent is ready
+/// with the required policy loaded
+async fn preflight(database: &Databases, opa_client: &OpaClient) -> Result<(), StartupError> {
+    database
+        .ping()
+        .await
+        .map_err(|err| StartupError::Database(err.into()))?;
+    database
+        .check_schema()
+        .await
+        .map_err(|err| StartupError::Schema(err.into()))?;
+    opa_client
+        .ready()
+        .await
+        .map_err(|err| StartupError::Policy(err.into()))?;
+    Ok(())
+}
+
+/// Creates a connection pool to access the MySQL or Postgres database, as selected by the [`Url`] scheme
+#[instrument(skip(database_url))]
+async fn setup_database(
+    database_url: Url,
+    pool: &DatabasePoolArgs,
+    log: &DatabaseLogArgs,
+    statement_timeout: Option<Duration>,
+    read_only: bool,
+    startup_timeout: Duration,
+    retry: &RetryPolicy,
+) -> Result<DatabaseConnection, TransactionError<DbErr>> {
+    info!("Connecting to database at {database_url}");
+    let sqlx_error = |err| DbErr::Conn(RuntimeErr::SqlxError(err));
+    let mut connection_options = ConnectOptions::new(database_url.to_string());
+    pool.configure(&mut connection_options);
+    let connection = match database_url.scheme() {
+        "postgres" | "postgresql" => {
+            let connect_options = log
+                .configure(PgConnectOptions::from_str(database_url.as_str()).map_err(sqlx_error)?);
+            let pool_options = connection_options.pool_options::<Postgres>().after_connect(
+                move |connection, _| {
+                    Box::pin(async move {
+                        if let Some(statement_timeout) = statement_timeout {
+                            set_postgres_statement_timeout(connection, statement_timeout).await?;
+                        }
+                        if read_only {
+                            set_postgres_read_only(connection).await?;
+                        }
+                        Ok(())
+                    })
+                },
+            );
+            SqlxPostgresConnector::from_sqlx_postgres_pool(
+                connect_pool(pool_options, connect_options, startup_timeout, retry).await,
+            )
+        }
+        _ => {
+            let connect_options = log.configure(
+                MySqlConnectOptions::from_str(database_url.as_str()).map_err(sqlx_error)?,
+            );
+            let pool_options =
+                connection_options
+                    .pool_options::<MySql>()
+                    .after_connect(move |connection, _| {
+                        Box::pin(async move {
+                            if let Some(statement_timeout) = statement_timeout {
+                                set_mysql_statement_timeout(connection, statement_timeout).await?;
+                            }
+                            if read_only {
+                                set_mysql_read_only(connection).await?;
+                            }
+                            Ok(())
+                        })
+                    });
+            SqlxMySqlConnector::from_sqlx_mysql_pool(
+                connect_pool(pool_options, connect_options, startup_timeout, retry).await,
+            )
+        }
+    };
+    info!("Database connection established: {connection:?}");
+    Ok(connection)
+}
+
+/// Serves the probes and metrics on the listener, apart from the GraphQL API, until a shutdown signal is received
+async fn serve_admin(router: Router, listener: TcpListener) -> Result<(), std::io::Error> {
+    axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+}
+
+/// Serves the gRPC API on the listener until a shutdown signal is received
+async fn serve_grpc(
+    server: SessionServiceServer<SessionGrpc>,
+    listener: TcpListener,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(server)
+        .serve_with_incoming_shutdown(
+            TcpIncoming::from_listener(listener, true, None)
+                .expect("Bound listeners should have an address"),
+            shutdown_signal(),
+        )
+        .await
+}
+
+/// Serves the endpoints on the specified address, over TLS if configured, until a shutdown signal is received, then stops
+/// accepting connections and waits up to the shutdown timeout for in-flight requests to complete
+///
+/// TLS is not supported on Unix domain sockets, which are expected to be fronted by a local reverse proxy
+async fn serve(
+    router: Router,
+    listen: Listen,
+    tls: Option<RustlsConfig>,
+    shutdown_timeout: Duration,
+) -> Result<(), std::io::Error> {
+    let socket_addr = match listen {
+        Listen::Tcp(socket_addr) => socket_addr,
+        Listen::Unix(_) if tls.is_some() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "TLS cannot be served on a Unix domain socket",
+            ))
+        }
+        Listen::Unix(path) => {
+            println!("Serving API & GraphQL UI at {}", Listen::Unix(path.clone()));
+            return serve_unix(router, &path, shutdown_signal(), shutdown_timeout).await;
+        }
+    };
+    if let Some(tls) = tls {
+        let handle = axum_server::Handle::new();
+        tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                shutdown_signal().await;
+                info!("Shutting down, waiting up to {shutdown_timeout:?} for in-flight requests");
+                handle.graceful_shutdown(Some(shutdown_timeout));
+            }
+        });
+        println!("Serving API & GraphQL UI over TLS at {}", socket_addr);
+        return axum_server::bind_rustls(socket_addr, tls)
+            .handle(handle)
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+            .await;
+    }
+    let listener = TcpListener::bind(socket_addr).await?;
+    println!("Serving API & GraphQL UI at {}", socket_addr);
+    let signal = shutdown_signal().shared();
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(signal.clone())
+    .into_future();
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => result,
+        () = signal => {
+            info!("Shutting down, waiting up to {shutdown_timeout:?} for in-flight requests");
+            match tokio::time::timeout(shutdown_timeout, server).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!("In-flight requests did not complete within {shutdown_timeout:?}");
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Completes when the process receives an interrupt or, on Unix, a terminate signal
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("Could not listen for interrupt signal: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("Could not listen for terminate signal: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = interrupt => {},
+        () = terminate => {},
+    }
+}
+
+/// Reports panics, and events logged as errors, to the Sentry project if a Data Source Name is provided, returning a guard
+/// which flushes pending reports when dropped
+fn setup_error_reporting(
+    dsn: Option<sentry::types::Dsn>,
+    deployment_environment: Option<String>,
+) -> Option<sentry::ClientInitGuard> {
+    dsn.map(|dsn| {
+        sentry::init(sentry::ClientOptions {
+            dsn: Some(dsn),
+            release: Some(built_info::PKG_VERSION.into()),
+            environment: deployment_environment.map(Into::into),
+            ..Default::default()
+        })
+    })
+}
+
+/// Sets up Logging & Tracing using opentelemetry if available, returning handles on the [`Telemetry`] pipelines
+///
+/// Logs are written to stdout in the [`LogFormat`], and traces are sent to the collector as selected by the [`opentelemetry_sdk::trace::Sampler`]
+///
+/// Traces and metrics carry the service name and version, and the deployment environment if provided, overridden and extended by
+/// any attributes in the standard `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_SERVICE_NAME` environment variables
+///
+/// Spans and events emitted whilst handling probe requests are discarded, such that frequent polling does not drown them out
+///
+/// Events logged as errors are reported to Sentry, with earlier events as breadcrumbs, if error reporting is enabled
+///
+/// Authorization audit records are always emitted, regardless of the log level, and are additionally appended to the audit log file if provided
+#[allow(clippy::too_many_arguments)]
+fn setup_telemetry(
+    log_level: tracing::Level,
+    log_format: LogFormat,
+    otel_collector_url: Option<Url>,
+    error_reporting: bool,
+    deployment_environment: Option<String>,
+    trace_sampler: opentelemetry_sdk::trace::Sampler,
+    prometheus_metrics: bool,
+    audit_log: Option<PathBuf>,
+) -> Result<Telemetry, anyhow::Error> {
+    let (level_filter, log_level) = LogLevel::new(log_level);
+    let (text_log_layer, json_log_layer) = match log_format {
+        LogFormat::Text => (Some(tracing_subscriber::fmt::layer()), None),
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false),
+            ),
+        ),
+    };
+    let audit_layer = audit_log
+        .map(|path| {
+            Ok::<_, std::io::Error>(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Arc::new(
+                        File::options().create(true).append(true).open(path)?,
+                    ))
+                    .with_filter(
+                        tracing_subscriber::filter::Targets::new()
+                            .with_target(AUDIT_TARGET, tracing::Level::INFO),
+                    ),
+            )
+        })
+        .transpose()?;
+    let service_resource = opentelemetry_sdk::Resource::new(
+        [
+            opentelemetry::KeyValue::new(
+                opentelemetry_semantic_conventions::resource::SERVICE_NAME,
+                built_info::PKG_NAME,
+            ),
+            opentelemetry::KeyValue::new(
+                opentelemetry_semantic_conventions::resource::SERVICE_VERSION,
+                built_info::PKG_VERSION,
+            ),
+        ]
+        .into_iter()
+        .chain(deployment_environment.map(|environment| {
+            opentelemetry::KeyValue::new(
+                opentelemetry_semantic_conventions::resource::DEPLOYMENT_ENVIRONMENT,
+                environment,
+            )
+        })),
+    )
+    .merge(&opentelemetry_sdk::Resource::from_detectors(
+        Duration::ZERO,
+        vec![Box::new(
+            opentelemetry_sdk::resource::EnvResourceDetector::new(),
+        )],
+    ))
+    .merge(&opentelemetry_sdk::Resource::new(
+        std::env::var("OTEL_SERVICE_NAME")
+            .ok()
+            .filter(|service_name| !service_name.is_empty())
+            .map(|service_name| {
+                opentelemetry::KeyValue::new(
+                    opentelemetry_semantic_conventions::resource::SERVICE_NAME,
+                    service_name,
+                )
+            }),
+    ));
+    let prometheus_registry = prometheus_metrics.then(prometheus::Registry::new);
+    let mut meter_provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
+        .with_resource(service_resource.clone());
+    if let Some(registry) = &prometheus_registry {
+        meter_provider = meter_provider.with_reader(
+            opentelemetry_prometheus::exporter()
+                .with_registry(registry.clone())
+                .build()?,
+        );
+    }
+    if let Some(otel_collector_url) = &otel_collector_url {
+        meter_provider = meter_provider.with_reader(
+            opentelemetry_sdk::metrics::PeriodicReader::builder(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(otel_collector_url.clone())
+                    .build_metrics_exporter(
+                        Box::new(
+                            opentelemetry_sdk::metrics::reader::DefaultAggregationSelector::new(),
+                        ),
+                        Box::new(
+                            opentelemetry_sdk::metrics::reader::DefaultTemporalitySelector::new(),
+                        ),
+                    )?,
+                opentelemetry_sdk::runtime::Tokio,
+            )
+            .with_interval(Duration::from_secs(10))
+            .build(),
+        );
+    }
+    let meter_provider =
+        (prometheus_registry.is_some() || otel_collector_url.is_some()).then(|| {
+            let meter_provider = meter_provider.build();
+            opentelemetry::global::set_meter_provider(meter_provider.clone());
+            meter_provider
+        });
+    let tracing_layer = if let Some(otel_collector_url) = otel_collector_url {
+        opentelemetry::global::set_text_map_propagator(
+            opentelemetry::propagation::TextMapCompositePropagator::new(vec![
+                Box::new(opentelemetry_sdk::propagation::TraceContextPropagator::default()),
+                Box::new(opentelemetry_sdk::propagation::BaggagePropagator::default()),
+            ]),
+        );
+        Some(
+            tracing_opentelemetry::layer().with_tracer(
+                opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_exporter(
+                        opentelemetry_otlp::new_exporter()
+                            .tonic()
+                            .with_endpoint(otel_collector_url),
+                    )
+                    .with_trace_config(
+                        opentelemetry_sdk::trace::config()
+                            .with_resource(service_resource)
+                            .with_sampler(trace_sampler),
+                    )
+                    .install_batch(opentelemetry_sdk::runtime::Tokio)?,
+            ),
+        )
+    } else {
+        None
+    };
+
+    tracing_subscriber::Registry::default()
+        .with(level_filter)
+        .with(tracing_subscriber::filter::dynamic_filter_fn(|_, _| {
+            !is_probing()
+        }))
+        .with(text_log_layer)
+        .with(json_log_layer)
+        .with(audit_layer)
+        .with(tracing_layer)
+        .with(error_reporting.then(sentry::integrations::tracing::layer))
+        .init();
+
+    Ok(Telemetry {
+        meter_provider,
+        prometheus_registry,
+        log_level,
+    })
+}
+
+/// Handles on the telemetry pipelines, by which metrics are served and exporters are flushed
+struct Telemetry {
+    /// The provider of all metrics, if any are exported
+    meter_provider: Option<opentelemetry_sdk::metrics::SdkMeterProvider>,
+    /// The registry of metrics to be scraped by Prometheus, if enabled
+    prometheus_registry: Option<prometheus::Registry>,
+    /// The handle by which the level of logs is changed
+    log_level: LogLevel,
+}
+
+impl Telemetry {
+    /// Flushes any buffered spans and metrics to their exporters and shuts them down
+    async fn shutdown(self) {
+        let meter_provider = self.meter_provider;
+        tokio::task::spawn_blocking(move || {
+            opentelemetry::global::shutdown_tracer_provider();
+            if let Some(meter_provider) = meter_provider {
+                if let Err(err) = meter_provider.shutdown() {
+                    warn!("Could not flush metrics: {err}");
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|err| warn!("Could not flush telemetry: {err}"));
+    }
+}
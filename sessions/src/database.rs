@@ -0,0 +1,577 @@
+use crate::{metrics::Metrics, opa::RetryPolicy};
+use futures::{stream::BoxStream, StreamExt};
+use models::{bl_session, proposal};
+use opentelemetry::{metrics::AsyncInstrument, KeyValue};
+use sea_orm::{
+    AccessMode, ConnAcquireErr, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    DbBackend, DbErr, EntityTrait, ExecResult, IdenStatic, IsolationLevel, Iterable, QueryResult,
+    RuntimeErr, Statement, StreamTrait, TransactionTrait,
+};
+use sqlx::{
+    mysql::{MySqlConnection, MySqlDatabaseError},
+    pool::PoolOptions,
+    postgres::{PgConnection, PgDatabaseError},
+    Connection, Database, Error as SqlxError, Pool,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::OnceCell;
+use tracing::{error, info, warn};
+
+/// The interval at which the time taken to acquire a connection from each pool is sampled
+const ACQUIRE_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The number of open and of idle connections in the pool of the connection, if it is pooled
+fn pool_connections(connection: &DatabaseConnection) -> Option<(u32, usize)> {
+    match connection {
+        DatabaseConnection::SqlxMySqlPoolConnection(_) => {
+            let pool = connection.get_mysql_connection_pool();
+            Some((pool.size(), pool.num_idle()))
+        }
+        DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+            let pool = connection.get_postgres_connection_pool();
+            Some((pool.size(), pool.num_idle()))
+        }
+        DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+            let pool = connection.get_sqlite_connection_pool();
+            Some((pool.size(), pool.num_idle()))
+        }
+        _ => None,
+    }
+}
+
+/// Acquires and immediately releases a connection from the pool of the connection, if it is pooled
+async fn acquire(connection: &DatabaseConnection) -> Result<(), SqlxError> {
+    match connection {
+        DatabaseConnection::SqlxMySqlPoolConnection(_) => {
+            connection.get_mysql_connection_pool().acquire().await?;
+        }
+        DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+            connection.get_postgres_connection_pool().acquire().await?;
+        }
+        DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+            connection.get_sqlite_connection_pool().acquire().await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The maximum time to wait for each database connection to respond to a ping
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// MySQL and MariaDB error numbers of failures which may succeed if retried, namely server shutdown (1053),
+/// lock wait timeout (1205), deadlock (1213), connection killed (1927), server gone away (2006) and lost connection (2013)
+const TRANSIENT_ERROR_NUMBERS: [u16; 6] = [1053, 1205, 1213, 1927, 2006, 2013];
+
+/// Postgres error codes of failures which may succeed if retried, namely serialization failure (40001),
+/// deadlock (40P01), administrator shutdown (57P01), crash shutdown (57P02) and unavailability (57P03)
+const TRANSIENT_ERROR_CODES: [&str; 5] = ["40001", "40P01", "57P01", "57P02", "57P03"];
+
+/// Whether a failed database operation may succeed if retried, i.e. a connection failure, deadlock or failover
+fn is_transient(error: &DbErr) -> bool {
+    match error {
+        DbErr::ConnectionAcquire(_) => true,
+        DbErr::Conn(RuntimeErr::SqlxError(error))
+        | DbErr::Exec(RuntimeErr::SqlxError(error))
+        | DbErr::Query(RuntimeErr::SqlxError(error)) => match error {
+            SqlxError::Io(_) | SqlxError::PoolTimedOut | SqlxError::WorkerCrashed => true,
+            SqlxError::Database(error) => {
+                error
+                    .try_downcast_ref::<MySqlDatabaseError>()
+                    .is_some_and(|error| TRANSIENT_ERROR_NUMBERS.contains(&error.number()))
+                    || error
+                        .try_downcast_ref::<PgDatabaseError>()
+                        .is_some_and(|error| {
+                            TRANSIENT_ERROR_CODES.contains(&error.code())
+                                || error.code().starts_with("08")
+                        })
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Limits the execution time of each statement on the connection, using the MariaDB `max_statement_time`
+/// session variable or, where it is not recognised, the MySQL `max_execution_time` session variable
+pub async fn set_mysql_statement_timeout(
+    connection: &mut MySqlConnection,
+    timeout: Duration,
+) -> Result<(), SqlxError> {
+    let mariadb = format!("SET SESSION max_statement_time = {}", timeout.as_secs_f64());
+    if sqlx::query(&mariadb)
+        .execute(&mut *connection)
+        .await
+        .is_err()
+    {
+        let mysql = format!("SET SESSION max_execution_time = {}", timeout.as_millis());
+        sqlx::query(&mysql).execute(connection).await?;
+    }
+    Ok(())
+}
+
+/// Limits the execution time of each statement on the connection, using the Postgres `statement_timeout` setting
+pub async fn set_postgres_statement_timeout(
+    connection: &mut PgConnection,
+    timeout: Duration,
+) -> Result<(), SqlxError> {
+    let statement = format!("SET statement_timeout = {}", timeout.as_millis());
+    sqlx::query(&statement).execute(connection).await?;
+    Ok(())
+}
+
+/// Restricts the connection to read-only transactions
+pub async fn set_mysql_read_only(connection: &mut MySqlConnection) -> Result<(), SqlxError> {
+    sqlx::query("SET SESSION TRANSACTION READ ONLY")
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+/// Restricts the connection to read-only transactions, using the Postgres `default_transaction_read_only` setting
+pub async fn set_postgres_read_only(connection: &mut PgConnection) -> Result<(), SqlxError> {
+    sqlx::query("SET default_transaction_read_only = on")
+        .execute(connection)
+        .await?;
+    Ok(())
+}
+
+/// MySQL privileges which permit modification of data or schema
+const WRITE_PRIVILEGES: &[&str] = &[
+    "ALL",
+    "ALL PRIVILEGES",
+    "ALTER",
+    "CREATE",
+    "DELETE",
+    "DROP",
+    "INSERT",
+    "UPDATE",
+];
+
+/// The table of the entity and the columns of it which are queried
+fn entity_columns<E: EntityTrait>(entity: E) -> (String, Vec<String>) {
+    (
+        entity.table_name().to_string(),
+        E::Column::iter()
+            .map(|column| column.as_str().to_string())
+            .collect(),
+    )
+}
+
+/// The tables and columns of the entities queried by the service, which the database must have, such that partial
+/// mirrors of ISPyB holding only sessions and proposals may be served
+fn entity_schema() -> Vec<(String, Vec<String>)> {
+    vec![
+        entity_columns(bl_session::Entity),
+        entity_columns(proposal::Entity),
+    ]
+}
+
+/// The columns of the table or view in the database, which are empty if it does not exist
+async fn table_columns(connection: &DatabaseConnection, table: &str) -> Result<Vec<String>, DbErr> {
+    let backend = connection.get_database_backend();
+    let sql = match backend {
+        DbBackend::MySql => {
+            "SELECT CAST(COLUMN_NAME AS CHAR) FROM information_schema.columns \
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?"
+        }
+        DbBackend::Postgres => {
+            "SELECT column_name::text FROM information_schema.columns \
+            WHERE table_schema = current_schema() AND table_name = $1"
+        }
+        DbBackend::Sqlite => "SELECT name FROM pragma_table_info(?)",
+    };
+    connection
+        .query_all(Statement::from_sql_and_values(backend, sql, [table.into()]))
+        .await?
+        .into_iter()
+        .map(|row| row.try_get_by_index::<String>(0))
+        .collect()
+}
+
+/// Lists the tables, and the columns of the tables which exist, of the entities which are missing from the database
+async fn missing_columns(connection: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    let mut missing = Vec::new();
+    for (table, columns) in entity_schema() {
+        let found = table_columns(connection, &table).await?;
+        if found.is_empty() {
+            missing.push(format!("table {table}"));
+            continue;
+        }
+        missing.extend(
+            columns
+                .into_iter()
+                .filter(|column| !found.iter().any(|found| found.eq_ignore_ascii_case(column)))
+                .map(|column| format!("column {table}.{column}")),
+        );
+    }
+    Ok(missing)
+}
+
+/// Lists the grants held by the user of the connection which permit writes, as reported by the database
+async fn write_grants(connection: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    let backend = connection.get_database_backend();
+    match backend {
+        DbBackend::MySql => Ok(connection
+            .query_all(Statement::from_string(backend, "SHOW GRANTS"))
+            .await?
+            .into_iter()
+            .filter_map(|row| row.try_get_by_index::<String>(0).ok())
+            .filter(|grant| {
+                grant
+                    .strip_prefix("GRANT ")
+                    .and_then(|grant| grant.split_once(" ON "))
+                    .is_some_and(|(privileges, _)| {
+                        privileges
+                            .split(',')
+                            .any(|privilege| WRITE_PRIVILEGES.contains(&privilege.trim()))
+                    })
+            })
+            .collect()),
+        DbBackend::Postgres => Ok(connection
+            .query_all(Statement::from_string(
+                backend,
+                "SELECT privilege || ' ON ' || table_schema || '.' || table_name \
+                FROM information_schema.tables, unnest(ARRAY['INSERT', 'UPDATE', 'DELETE', 'TRUNCATE']) AS privilege \
+                WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+                AND has_table_privilege(quote_ident(table_schema) || '.' || quote_ident(table_name), privilege)",
+            ))
+            .await?
+            .into_iter()
+            .filter_map(|row| row.try_get_by_index::<String>(0).ok())
+            .collect()),
+        DbBackend::Sqlite => Ok(Vec::new()),
+    }
+}
+
+/// Creates a connection pool, retrying until the startup timeout elapses before falling back to establishing
+/// connections lazily, such that an unreachable database is reported by the readiness probe rather than preventing startup
+pub async fn connect_pool<DB: Database>(
+    pool_options: PoolOptions<DB>,
+    connect_options: <DB::Connection as Connection>::Options,
+    startup_timeout: Duration,
+    retry: &RetryPolicy,
+) -> Pool<DB> {
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        match pool_options
+            .clone()
+            .connect_with(connect_options.clone())
+            .await
+        {
+            Ok(pool) => return pool,
+            Err(err) => {
+                attempt += 1;
+                let delay = retry.delay(attempt);
+                if start.elapsed() + delay > startup_timeout {
+                    warn!("Could not connect to database, connecting lazily: {err}");
+                    return pool_options.connect_lazy_with(connect_options);
+                }
+                warn!("Could not connect to database, retrying in {delay:?}: {err}");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// A connection on which read-only queries are made
+#[derive(Debug, Clone)]
+pub enum ReadConnection {
+    /// A connection from the pool of the primary or of a replica
+    Pool(DatabaseConnection),
+    /// The transaction in which the reads of a snapshot are made
+    Snapshot(Arc<DatabaseTransaction>),
+}
+
+#[async_trait::async_trait]
+impl ConnectionTrait for ReadConnection {
+    fn get_database_backend(&self) -> DbBackend {
+        match self {
+            Self::Pool(connection) => connection.get_database_backend(),
+            Self::Snapshot(transaction) => transaction.get_database_backend(),
+        }
+    }
+
+    async fn execute(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
+        match self {
+            Self::Pool(connection) => connection.execute(stmt).await,
+            Self::Snapshot(transaction) => transaction.execute(stmt).await,
+        }
+    }
+
+    async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
+        match self {
+            Self::Pool(connection) => connection.execute_unprepared(sql).await,
+            Self::Snapshot(transaction) => transaction.execute_unprepared(sql).await,
+        }
+    }
+
+    async fn query_one(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
+        match self {
+            Self::Pool(connection) => connection.query_one(stmt).await,
+            Self::Snapshot(transaction) => transaction.query_one(stmt).await,
+        }
+    }
+
+    async fn query_all(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+        match self {
+            Self::Pool(connection) => connection.query_all(stmt).await,
+            Self::Snapshot(transaction) => transaction.query_all(stmt).await,
+        }
+    }
+}
+
+impl StreamTrait for ReadConnection {
+    type Stream<'a> = BoxStream<'a, Result<QueryResult, DbErr>>;
+
+    fn stream<'a>(
+        &'a self,
+        stmt: Statement,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Stream<'a>, DbErr>> + 'a + Send>> {
+        Box::pin(async move {
+            Ok(match self {
+                Self::Pool(connection) => connection.stream(stmt).await?.boxed(),
+                Self::Snapshot(transaction) => transaction.stream(stmt).await?.boxed(),
+            })
+        })
+    }
+}
+
+/// Connections to the primary database and any read replicas, clones of which share the replicas' load balancing
+#[derive(Debug, Clone)]
+pub struct Databases {
+    /// The primary database, to which any writes are to be made
+    primary: DatabaseConnection,
+    /// The read replicas, between which reads are balanced
+    replicas: Arc<[DatabaseConnection]>,
+    /// The index of the next replica to read from
+    next: Arc<AtomicUsize>,
+    /// How transiently failing operations are retried
+    retry: RetryPolicy,
+    /// The transaction in which all reads are made, begun on the first read, if reading from a snapshot
+    snapshot: Option<Arc<OnceCell<Arc<DatabaseTransaction>>>>,
+}
+
+impl Databases {
+    /// Creates a [`Databases`] from connections to the primary and any read replicas
+    pub fn new(
+        primary: DatabaseConnection,
+        replicas: Vec<DatabaseConnection>,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            primary,
+            replicas: replicas.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+            retry,
+            snapshot: None,
+        }
+    }
+
+    /// Creates a clone of the [`Databases`] whose reads are all made in a single read-only `REPEATABLE READ`
+    /// transaction, begun on the first read, such that they observe a consistent snapshot of the database
+    ///
+    /// Reads within the snapshot are not retried, as a new transaction could observe a different snapshot
+    pub fn snapshot(&self) -> Self {
+        Self {
+            snapshot: Some(Arc::default()),
+            ..self.clone()
+        }
+    }
+
+    /// Ends the transaction of the snapshot, if one was begun, returning its connection to the pool
+    pub async fn end_snapshot(self) {
+        let transaction = self
+            .snapshot
+            .and_then(Arc::into_inner)
+            .and_then(OnceCell::into_inner)
+            .and_then(Arc::into_inner);
+        if let Some(transaction) = transaction {
+            if let Err(err) = transaction.commit().await {
+                warn!("Could not end snapshot transaction: {err}");
+            }
+        }
+    }
+
+    /// A connection for read-only queries, selecting the replicas in turn or the primary if there are none
+    fn replica(&self) -> &DatabaseConnection {
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        &self.replicas[index]
+    }
+
+    /// The primary and each replica connection, with a name identifying them in metrics
+    fn named_connections(&self) -> impl Iterator<Item = (String, &DatabaseConnection)> {
+        std::iter::once(("primary".to_string(), &self.primary)).chain(
+            self.replicas
+                .iter()
+                .enumerate()
+                .map(|(index, replica)| (format!("replica-{index}"), replica)),
+        )
+    }
+
+    /// Exports gauges of the open and idle connections in each pool, and periodically samples the time taken to acquire a connection
+    pub fn export_pool_metrics(&self) {
+        let meter = opentelemetry::global::meter(crate::built_info::PKG_NAME);
+        let databases = self.clone();
+        meter
+            .u64_observable_gauge("db_pool_connections")
+            .with_description("The number of open connections in the database pool")
+            .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+                for (name, connection) in databases.named_connections() {
+                    if let Some((size, _)) = pool_connections(connection) {
+                        observer.observe(size.into(), &[KeyValue::new("database", name)]);
+                    }
+                }
+            })
+            .init();
+        let databases = self.clone();
+        meter
+            .u64_observable_gauge("db_pool_idle_connections")
+            .with_description("The number of idle connections in the database pool")
+            .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+                for (name, connection) in databases.named_connections() {
+                    if let Some((_, idle)) = pool_connections(connection) {
+                        observer.observe(idle as u64, &[KeyValue::new("database", name)]);
+                    }
+                }
+            })
+            .init();
+        let databases = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ACQUIRE_SAMPLE_INTERVAL);
+            loop {
+                ticker.tick().await;
+                for (name, connection) in databases.named_connections() {
+                    let start = Instant::now();
+                    match acquire(connection).await {
+                        Ok(()) => Metrics::get().db_pool_acquire_wait_seconds.record(
+                            start.elapsed().as_secs_f64(),
+                            &[KeyValue::new("database", name)],
+                        ),
+                        Err(SqlxError::PoolTimedOut) => Metrics::get()
+                            .db_pool_acquire_timeouts
+                            .add(1, &[KeyValue::new("database", name)]),
+                        Err(err) => warn!("Could not acquire connection to {name}: {err}"),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Checks that a connection to the primary and to each replica can be acquired and used within the `PING_TIMEOUT`
+    pub async fn ping(&self) -> Result<(), DbErr> {
+        for connection in std::iter::once(&self.primary).chain(self.replicas.iter()) {
+            tokio::time::timeout(PING_TIMEOUT, connection.execute_unprepared("SELECT 1"))
+                .await
+                .map_err(|_| DbErr::Conn(RuntimeErr::Internal("Ping timed out".to_string())))??;
+        }
+        Ok(())
+    }
+
+    /// Checks that the primary and each replica have the tables and columns of the queried entities, logging each which
+    /// is missing
+    pub async fn check_schema(&self) -> Result<(), DbErr> {
+        for (name, connection) in self.named_connections() {
+            let missing = missing_columns(connection).await?;
+            if !missing.is_empty() {
+                for missing in &missing {
+                    error!("Schema of {name} is missing {missing}");
+                }
+                return Err(DbErr::Custom(format!(
+                    "Schema of {name} is missing {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+        info!("Database schema has the tables and columns of the queried entities");
+        Ok(())
+    }
+
+    /// Checks that the user of the primary and of each replica holds no grants which permit writes, which the API
+    /// should never require, failing if any does or if the grants cannot be verified
+    pub async fn check_write_grants(&self) -> Result<(), DbErr> {
+        for (name, connection) in self.named_connections() {
+            let grants = write_grants(connection).await.map_err(|err| {
+                DbErr::Custom(format!(
+                    "Could not verify grants of database user of {name}: {err}"
+                ))
+            })?;
+            if !grants.is_empty() {
+                return Err(DbErr::Custom(format!(
+                    "Database user of {name} holds write grants: {}",
+                    grants.join("; ")
+                )));
+            }
+            info!("Database user of {name} holds no write grants");
+        }
+        Ok(())
+    }
+
+    /// Performs a read-only query, within the snapshot if there is one, otherwise retrying according to the
+    /// [`RetryPolicy`] on the next replica if it fails transiently
+    pub async fn read<T, F, Fut>(&self, query: F) -> Result<T, DbErr>
+    where
+        F: Fn(ReadConnection) -> Fut,
+        Fut: Future<Output = Result<T, DbErr>>,
+    {
+        match &self.snapshot {
+            Some(snapshot) => {
+                let transaction = snapshot
+                    .get_or_try_init(|| {
+                        self.retrying(|connection| async move {
+                            connection
+                                .begin_with_config(
+                                    Some(IsolationLevel::RepeatableRead),
+                                    Some(AccessMode::ReadOnly),
+                                )
+                                .await
+                                .map(Arc::new)
+                        })
+                    })
+                    .await?;
+                query(ReadConnection::Snapshot(transaction.clone())).await
+            }
+            None => {
+                self.retrying(|connection| query(ReadConnection::Pool(connection)))
+                    .await
+            }
+        }
+    }
+
+    /// Performs an operation on a replica, retrying according to the [`RetryPolicy`] on the next replica if it fails transiently
+    async fn retrying<T, F, Fut>(&self, operation: F) -> Result<T, DbErr>
+    where
+        F: Fn(DatabaseConnection) -> Fut,
+        Fut: Future<Output = Result<T, DbErr>>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = operation(self.replica().clone()).await;
+            if let Err(DbErr::ConnectionAcquire(ConnAcquireErr::Timeout)) = result {
+                Metrics::get().db_pool_acquire_timeouts.add(1, &[]);
+            }
+            match result {
+                Err(err) if is_transient(&err) && retry + 1 < self.retry.attempts => {
+                    retry += 1;
+                    let delay = self.retry.delay(retry);
+                    warn!("Database query failed, retrying in {delay:?}: {err}");
+                    Metrics::get().database_query_retries.add(1, &[]);
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
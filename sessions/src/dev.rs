@@ -0,0 +1,62 @@
+use crate::fixtures::Fixtures;
+use models::{
+    bl_sample, bl_session, container, data_collection, data_collection_group, dewar, person,
+    proposal, session_has_person, session_type, shipping,
+};
+use sea_orm::{
+    sea_query::TableCreateStatement, ConnectionTrait, DatabaseConnection, DbBackend, DbErr,
+    RuntimeErr, Schema, SqlxSqliteConnector,
+};
+use sqlx::sqlite::SqlitePoolOptions;
+use tracing::{info, instrument};
+
+/// The `v_session` view of ISPyB, joining each session to its proposal
+const SESSION_VIEW: &str = "CREATE VIEW v_session AS SELECT \
+    BLSession.sessionId AS sessionId, \
+    BLSession.proposalId AS proposalId, \
+    BLSession.startDate AS BLSession_startDate, \
+    BLSession.endDate AS BLSession_endDate, \
+    BLSession.beamLineName AS beamLineName, \
+    BLSession.visit_number AS visit_number, \
+    Proposal.proposalId AS Proposal_proposalId, \
+    Proposal.proposalCode AS proposalCode, \
+    Proposal.proposalNumber AS proposalNumber, \
+    Proposal.title AS title \
+    FROM BLSession LEFT JOIN Proposal ON BLSession.proposalId = Proposal.proposalId";
+
+/// Creates an in-memory SQLite database with the tables of every entity, seeded with the fixtures
+#[instrument(skip_all)]
+pub async fn seed_database(fixtures: &Fixtures) -> Result<DatabaseConnection, DbErr> {
+    info!("Creating in-memory development database");
+    let pool = SqlitePoolOptions::new()
+        .min_connections(1)
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .map_err(|err| DbErr::Conn(RuntimeErr::SqlxError(err)))?;
+    let database = SqlxSqliteConnector::from_sqlx_sqlite_pool(pool);
+    let schema = Schema::new(DbBackend::Sqlite);
+    let tables: [TableCreateStatement; 11] = [
+        schema.create_table_from_entity(person::Entity),
+        schema.create_table_from_entity(proposal::Entity),
+        schema.create_table_from_entity(bl_session::Entity),
+        schema.create_table_from_entity(session_has_person::Entity),
+        schema.create_table_from_entity(session_type::Entity),
+        schema.create_table_from_entity(data_collection_group::Entity),
+        schema.create_table_from_entity(data_collection::Entity),
+        schema.create_table_from_entity(shipping::Entity),
+        schema.create_table_from_entity(dewar::Entity),
+        schema.create_table_from_entity(container::Entity),
+        schema.create_table_from_entity(bl_sample::Entity),
+    ];
+    for table in tables {
+        database
+            .execute(database.get_database_backend().build(&table))
+            .await?;
+    }
+    database.execute_unprepared(SESSION_VIEW).await?;
+    fixtures.insert(&database).await?;
+    Ok(database)
+}
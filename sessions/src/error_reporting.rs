@@ -0,0 +1,60 @@
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute},
+    PathSegment, Response, ServerError, Value,
+};
+use std::sync::Arc;
+use tracing::error;
+
+/// The error codes of failures caused by the request, rather than by a fault in the service or its dependencies
+const EXPECTED_CODES: &[&str] = &["FORBIDDEN"];
+
+/// An [`ExtensionFactory`] logging errors raised by resolvers which do not stem from the request, such that they are reported
+/// to the on-call channel when an error reporting service is configured
+///
+/// Errors in parsing or validation, and authorization denials, are not reported
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorReporting;
+
+impl ExtensionFactory for ErrorReporting {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(*self)
+    }
+}
+
+/// Whether the error was raised by a resolver and does not carry the code of an expected failure
+fn is_unexpected(error: &ServerError) -> bool {
+    let code = error
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"));
+    !error.path.is_empty()
+        && !matches!(code, Some(Value::String(code)) if EXPECTED_CODES.contains(&code.as_str()))
+}
+
+#[async_trait::async_trait]
+impl Extension for ErrorReporting {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let response = next.run(ctx, operation_name).await;
+        for err in response.errors.iter().filter(|err| is_unexpected(err)) {
+            let path = err
+                .path
+                .iter()
+                .map(|segment| match segment {
+                    PathSegment::Field(name) => name.clone(),
+                    PathSegment::Index(index) => index.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(".");
+            error!(
+                operation = operation_name.unwrap_or_default(),
+                path, "Resolver failed: {}", err.message
+            );
+        }
+        response
+    }
+}
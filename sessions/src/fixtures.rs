@@ -0,0 +1,94 @@
+use models::{
+    bl_session, data_collection, data_collection_group, person, proposal, session_has_person,
+    session_type,
+};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel};
+use serde::Deserialize;
+use std::{fmt::Display, fs::File, path::Path};
+use tracing::info;
+
+/// The fixtures seeded into the development database unless others are provided
+const DEVELOPMENT_FIXTURES: &str = include_str!("../fixtures/dev.yaml");
+
+/// Rows of the ISPyB tables to be seeded into a database, each listed by the snake case names of the fields of its entity
+///
+/// Rows are inserted table by table in the order of the fields, such that each may reference those of preceding tables
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fixtures {
+    /// Rows of the `Person` table
+    people: Vec<person::Model>,
+    /// Rows of the `Proposal` table
+    proposals: Vec<proposal::Model>,
+    /// Rows of the `BLSession` table
+    sessions: Vec<bl_session::Model>,
+    /// Rows of the `Session_has_Person` table
+    session_people: Vec<session_has_person::Model>,
+    /// Rows of the `SessionType` table
+    session_types: Vec<session_type::Model>,
+    /// Rows of the `DataCollectionGroup` table
+    data_collection_groups: Vec<data_collection_group::Model>,
+    /// Rows of the `DataCollection` table
+    data_collections: Vec<data_collection::Model>,
+}
+
+impl Fixtures {
+    /// The representative people, proposals and sessions of the development database
+    pub fn development() -> Self {
+        serde_yaml::from_str(DEVELOPMENT_FIXTURES).expect("Development fixtures should be valid")
+    }
+
+    /// Loads the fixtures from a YAML, or JSON, file
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        Ok(serde_yaml::from_reader(File::open(path)?)?)
+    }
+
+    /// Inserts every row of the fixtures into the database
+    pub async fn insert(&self, connection: &impl ConnectionTrait) -> Result<(), DbErr> {
+        insert_all::<person::ActiveModel>(connection, &self.people).await?;
+        insert_all::<proposal::ActiveModel>(connection, &self.proposals).await?;
+        insert_all::<bl_session::ActiveModel>(connection, &self.sessions).await?;
+        insert_all::<session_has_person::ActiveModel>(connection, &self.session_people).await?;
+        insert_all::<session_type::ActiveModel>(connection, &self.session_types).await?;
+        insert_all::<data_collection_group::ActiveModel>(connection, &self.data_collection_groups)
+            .await?;
+        insert_all::<data_collection::ActiveModel>(connection, &self.data_collections).await?;
+        info!("Seeded {self}");
+        Ok(())
+    }
+}
+
+impl Display for Fixtures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} people, {} proposals and {} sessions",
+            self.people.len(),
+            self.proposals.len(),
+            self.sessions.len()
+        )
+    }
+}
+
+/// Inserts the rows into the table of the entity, if there are any
+async fn insert_all<A>(
+    connection: &impl ConnectionTrait,
+    models: &[<A::Entity as EntityTrait>::Model],
+) -> Result<(), DbErr>
+where
+    A: ActiveModelTrait,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+{
+    if models.is_empty() {
+        return Ok(());
+    }
+    A::Entity::insert_many(
+        models
+            .iter()
+            .cloned()
+            .map(IntoActiveModel::into_active_model),
+    )
+    .exec(connection)
+    .await?;
+    Ok(())
+}
@@ -1,24 +1,44 @@
-use crate::opa::{OpaClient, OpaInput};
+use crate::{
+    cache::{digest_key, Cache},
+    database::Databases,
+    opa::{request_subject, OpaClient, OpaGuard, OpaInput, OpaPartialInput, ParameterColumn},
+};
 use async_graphql::{
-    ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Schema, SchemaBuilder,
-    SimpleObject,
+    ComplexObject, Context, EmptyMutation, ErrorExtensions, Object, ResultExt, Schema,
+    SchemaBuilder, SimpleObject, Subscription,
 };
 use chrono::{DateTime, Utc};
-use models::{bl_session, proposal};
-use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
-use serde::Serialize;
-use tracing::{info, instrument};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use models::{bl_session, proposal, sea_orm_active_enums::State};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, SelectTwo,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{info, instrument, warn};
+
+/// The number of candidate sessions streamed from the database and authorized in each batch, when the policy cannot be
+/// partially evaluated, which bounds the rows held in memory however many the caller may not view
+const AUTHORIZATION_CHUNK_SIZE: usize = 1000;
+
+/// The greatest number of sessions listed by a single `sessions` query, which is also the default limit, and so the
+/// greatest number of rows held in memory when the database filters them by the partially evaluated policy
+pub const MAX_SESSIONS: u64 = 1000;
+
+/// The interval at which the session of a `session` subscription is retrieved, to detect changes to it
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
 
 /// The GraphQL schema exposed by the service
-pub type RootSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+pub type RootSchema = Schema<Query, EmptyMutation, SubscriptionRoot>;
 
 /// A schema builder for the service
-pub fn root_schema_builder() -> SchemaBuilder<Query, EmptyMutation, EmptySubscription> {
-    Schema::build(Query, EmptyMutation, EmptySubscription).enable_federation()
+pub fn root_schema_builder() -> SchemaBuilder<Query, EmptyMutation, SubscriptionRoot> {
+    Schema::build(Query, EmptyMutation, SubscriptionRoot).enable_federation()
 }
 
 /// A Beamline Session
-#[derive(Debug, SimpleObject)]
+#[derive(Debug, Clone, PartialEq, SimpleObject, Serialize, Deserialize)]
 #[graphql(complex, unresolvable = "id")]
 struct Session {
     /// The underlying database model
@@ -28,6 +48,15 @@ struct Session {
     proposal: Option<Proposal>,
 }
 
+impl From<(bl_session::Model, Option<proposal::Model>)> for Session {
+    fn from((session, proposal): (bl_session::Model, Option<proposal::Model>)) -> Self {
+        Self {
+            session,
+            proposal: proposal.map(Proposal),
+        }
+    }
+}
+
 #[ComplexObject]
 impl Session {
     async fn id(&self, _ctx: &Context<'_>) -> u32 {
@@ -45,10 +74,15 @@ impl Session {
     async fn end(&self, _ctx: &Context<'_>) -> Option<DateTime<Utc>> {
         self.session.end_date.map(|date| date.and_utc())
     }
+
+    /// The name of the beamline on which the session takes place
+    async fn beamline(&self, _ctx: &Context<'_>) -> &Option<String> {
+        &self.session.beamline
+    }
 }
 
 /// An Experimental Proposal, containing numerous sessions
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct Proposal(proposal::Model);
 
 #[Object]
@@ -66,12 +100,26 @@ impl Proposal {
             .map(|num| num.parse())
             .transpose()?)
     }
+
+    /// Whether the Proposal is open, closed or cancelled
+    async fn state(&self, _ctx: &Context<'_>) -> Option<State> {
+        self.0.state
+    }
 }
 
+/// A [`Cache`] of the results of `session` queries, keyed by a digest of their arguments and the subject on whose behalf
+/// they were made
+#[derive(Debug, Clone)]
+pub struct SessionCache(pub Cache);
+
 /// The root query of the service
 #[derive(Debug, Clone, Default)]
 pub struct Query;
 
+/// The root subscription of the service
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionRoot;
+
 /// Parameters required to
 #[derive(Debug, Serialize)]
 struct OpaSessionParameters {
@@ -85,6 +133,13 @@ struct OpaSessionParameters {
 impl Query {
     /// Retrieves a Beamline Session
     #[instrument(name = "query_session", skip(ctx))]
+    #[graphql(cache_control(max_age = 60, private), guard = OpaGuard::new(
+        "sessions/read",
+        OpaSessionParameters {
+            proposal: proposal_number,
+            visit,
+        }
+    ))]
     async fn session(
         &self,
         ctx: &Context<'_>,
@@ -92,30 +147,279 @@ impl Query {
         proposal_number: u32,
         visit: u32,
     ) -> Result<Option<Session>, async_graphql::Error> {
-        let database = ctx.data::<DatabaseConnection>()?;
-        ctx.data::<OpaClient>()?
-            .decide(OpaInput::new(
-                ctx,
-                OpaSessionParameters {
-                    proposal: proposal_number,
-                    visit,
-                },
-            )?)
-            .await?;
+        let database = ctx.data::<Databases>()?;
+        let cache = ctx
+            .data_opt::<Option<SessionCache>>()
+            .and_then(Option::as_ref);
+        let key = digest_key(&(request_subject(ctx), &proposal_code, proposal_number, visit))?;
+        if let Some(cache) = cache {
+            if let Some(session) = cache.0.get::<Option<Session>>(&key).await {
+                return Ok(session);
+            }
+        }
         info!("Retrieving session");
-        Ok(bl_session::Entity::find()
-            .find_also_related(proposal::Entity)
-            .filter(
-                Condition::all()
-                    .add(proposal::Column::ProposalCode.eq(proposal_code))
-                    .add(proposal::Column::ProposalNumber.eq(proposal_number))
-                    .add(bl_session::Column::VisitNumber.eq(visit)),
-            )
-            .one(database)
+        let query = session_query(proposal_code, proposal_number, visit);
+        let session = database
+            .read(|connection| {
+                let query = query.clone();
+                async move { query.one(&connection).await }
+            })
             .await?
-            .map(|(session, proposal)| Session {
-                session,
-                proposal: proposal.map(Proposal),
-            }))
+            .map(Session::from);
+        if let Some(cache) = cache {
+            cache.0.insert(key, &session).await;
+        }
+        Ok(session)
+    }
+
+    /// Retrieves the Beamline Sessions the caller is permitted to view, in order of their identifiers, skipping the first
+    /// `offset` and listing at most `limit`, which may not exceed 1000
+    #[instrument(name = "query_sessions", skip(ctx))]
+    #[graphql(cache_control(max_age = 60, private))]
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        proposal_code: Option<String>,
+        proposal_number: Option<u32>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> Result<Vec<Session>, async_graphql::Error> {
+        let limit = limit.unwrap_or(MAX_SESSIONS);
+        if limit > MAX_SESSIONS {
+            return Err(
+                async_graphql::Error::new(format!("limit must not exceed {MAX_SESSIONS}"))
+                    .extend_with(|_, extensions| extensions.set("code", "BAD_USER_INPUT")),
+            );
+        }
+        let offset = offset.unwrap_or_default();
+        let database = ctx.data::<Databases>()?;
+        let opa_client = ctx.data::<OpaClient>()?;
+        let filter = Condition::all()
+            .add_option(proposal_code.map(|code| proposal::Column::ProposalCode.eq(code)))
+            .add_option(
+                proposal_number
+                    .map(|number| proposal::Column::ProposalNumber.eq(number.to_string())),
+            );
+        let permitted = opa_client
+            .compile(OpaPartialInput::new(ctx))
+            .await
+            .extend()?
+            .into_condition(|parameter| match parameter {
+                "proposal" => Some(ParameterColumn::Text(Expr::col((
+                    proposal::Entity,
+                    proposal::Column::ProposalNumber,
+                )))),
+                "visit" => Some(ParameterColumn::Native(Expr::col((
+                    bl_session::Entity,
+                    bl_session::Column::VisitNumber,
+                )))),
+                _ => None,
+            });
+        match permitted {
+            Ok(Some(permitted)) => {
+                info!("Retrieving sessions");
+                let query = bl_session::Entity::find()
+                    .find_also_related(proposal::Entity)
+                    .filter(filter.add(permitted))
+                    .order_by_asc(bl_session::Column::SessionId)
+                    .offset(offset)
+                    .limit(limit);
+                Ok(database
+                    .read(|connection| {
+                        let query = query.clone();
+                        async move { query.all(&connection).await }
+                    })
+                    .await?
+                    .into_iter()
+                    .map(Session::from)
+                    .collect())
+            }
+            Ok(None) => Ok(Vec::new()),
+            Err(err) => {
+                warn!("Falling back to batch authorization: {err}");
+                let query = bl_session::Entity::find()
+                    .find_also_related(proposal::Entity)
+                    .filter(filter)
+                    .order_by_asc(bl_session::Column::SessionId);
+                Ok(database
+                    .read(|connection| {
+                        let query = query.clone();
+                        async move {
+                            let mut chunks = query
+                                .stream(&connection)
+                                .await?
+                                .try_chunks(AUTHORIZATION_CHUNK_SIZE)
+                                .map_err(|err| err.1)
+                                .boxed();
+                            let (limit, mut offset) = (limit as usize, offset as usize);
+                            let mut sessions = Vec::new();
+                            while let Some(chunk) = chunks.try_next().await? {
+                                let (parameters, candidates): (Vec<_>, Vec<_>) = chunk
+                                    .into_iter()
+                                    .filter_map(|(session, proposal)| {
+                                        let parameters = OpaSessionParameters {
+                                            proposal: proposal
+                                                .as_ref()?
+                                                .proposal_number
+                                                .as_ref()?
+                                                .parse()
+                                                .ok()?,
+                                            visit: session.visit_number?,
+                                        };
+                                        Some((parameters, (session, proposal)))
+                                    })
+                                    .unzip();
+                                match opa_client
+                                    .decide_batch(OpaInput::new(ctx, parameters), candidates)
+                                    .await
+                                {
+                                    Ok(permitted) => {
+                                        let skipped = offset.min(permitted.len());
+                                        offset -= skipped;
+                                        sessions.extend(
+                                            permitted
+                                                .into_iter()
+                                                .skip(skipped)
+                                                .take(limit - sessions.len())
+                                                .map(Session::from),
+                                        );
+                                    }
+                                    Err(err) => return Ok(Err(err)),
+                                }
+                                if sessions.len() == limit {
+                                    break;
+                                }
+                            }
+                            Ok(Ok(sessions))
+                        }
+                    })
+                    .await?
+                    .extend()?)
+            }
+        }
+    }
+
+    /// Lists the names of all beamlines on which sessions have taken place
+    #[instrument(name = "query_beamlines", skip(ctx))]
+    #[graphql(cache_control(max_age = 300), guard = OpaGuard::public(()))]
+    async fn beamlines(&self, ctx: &Context<'_>) -> Result<Vec<String>, async_graphql::Error> {
+        let database = ctx.data::<Databases>()?;
+        info!("Retrieving beamlines");
+        let query = bl_session::Entity::find()
+            .select_only()
+            .column(bl_session::Column::Beamline)
+            .distinct()
+            .filter(bl_session::Column::Beamline.is_not_null())
+            .order_by_asc(bl_session::Column::Beamline);
+        Ok(database
+            .read(|connection| {
+                let query = query.clone();
+                async move { query.into_tuple::<String>().all(&connection).await }
+            })
+            .await?)
+    }
+
+    /// Counts the Beamline Sessions, optionally only those on a beamline
+    #[instrument(name = "query_session_count", skip(ctx))]
+    #[graphql(cache_control(max_age = 300), guard = OpaGuard::public(()))]
+    async fn session_count(
+        &self,
+        ctx: &Context<'_>,
+        beamline: Option<String>,
+    ) -> Result<u64, async_graphql::Error> {
+        let database = ctx.data::<Databases>()?;
+        info!("Counting sessions");
+        let query =
+            bl_session::Entity::find()
+                .filter(Condition::all().add_option(
+                    beamline.map(|beamline| bl_session::Column::Beamline.eq(beamline)),
+                ));
+        Ok(database
+            .read(|connection| {
+                let query = query.clone();
+                async move { query.count(&connection).await }
+            })
+            .await?)
+    }
+}
+
+#[Subscription(name = "Subscription")]
+impl SubscriptionRoot {
+    /// Watches a Beamline Session, yielding it once subscribed and again whenever it changes, as retrieved every ten
+    /// seconds, such that displays need not poll for it
+    #[graphql(guard = OpaGuard::new(
+        "sessions/read",
+        OpaSessionParameters {
+            proposal: proposal_number,
+            visit,
+        }
+    ))]
+    async fn session(
+        &self,
+        ctx: &Context<'_>,
+        proposal_code: String,
+        proposal_number: u32,
+        visit: u32,
+    ) -> Result<
+        impl Stream<Item = Result<Option<Session>, async_graphql::Error>>,
+        async_graphql::Error,
+    > {
+        let database = ctx.data::<Databases>()?.clone();
+        let query = session_query(proposal_code, proposal_number, visit);
+        let watch = (tokio::time::interval(WATCH_INTERVAL), None);
+        Ok(stream::unfold(watch, move |(mut interval, last)| {
+            let database = database.clone();
+            let query = query.clone();
+            async move {
+                loop {
+                    interval.tick().await;
+                    let session = database
+                        .read(|connection| {
+                            let query = query.clone();
+                            async move { query.one(&connection).await }
+                        })
+                        .await
+                        .map(|session| session.map(Session::from));
+                    match session {
+                        Ok(session) if last.as_ref() == Some(&session) => continue,
+                        Ok(session) => {
+                            return Some((Ok(session.clone()), (interval, Some(session))))
+                        }
+                        Err(err) => return Some((Err(err.into()), (interval, last))),
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// The query retrieving the session, with its proposal, of the proposal and visit
+fn session_query(
+    proposal_code: String,
+    proposal_number: u32,
+    visit: u32,
+) -> SelectTwo<bl_session::Entity, proposal::Entity> {
+    bl_session::Entity::find()
+        .find_also_related(proposal::Entity)
+        .filter(
+            Condition::all()
+                .add(proposal::Column::ProposalCode.eq(proposal_code))
+                .add(proposal::Column::ProposalNumber.eq(proposal_number.to_string()))
+                .add(bl_session::Column::VisitNumber.eq(visit)),
+        )
+}
+
+/// Snapshots of the schema, such that changes to it are deliberate
+#[cfg(test)]
+mod tests {
+    use super::root_schema_builder;
+    use async_graphql::SDLExportOptions;
+
+    #[test]
+    fn schema_matches_snapshot() {
+        let sdl = root_schema_builder()
+            .finish()
+            .sdl_with_options(SDLExportOptions::new().federation());
+        insta::assert_snapshot!(sdl);
     }
 }
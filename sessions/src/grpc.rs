@@ -0,0 +1,170 @@
+use crate::{
+    graphql::RootSchema,
+    route_handlers::{
+        error_status, rejection_message, GraphQLHandler, SESSIONS_QUERY, SESSION_QUERY,
+    },
+};
+use axum::{
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Response,
+};
+use serde_json::{json, Value};
+use tonic::{Code, Status};
+
+/// The messages and service of `proto/sessions.proto`, as generated by `tonic-build`
+#[allow(missing_docs, clippy::missing_docs_in_private_items)]
+pub mod proto {
+    tonic::include_proto!("sessions.v1");
+}
+
+use proto::{
+    session_service_server::{SessionService, SessionServiceServer},
+    GetSessionRequest, ListSessionsRequest, ListSessionsResponse, Proposal, Session,
+};
+
+/// The [`SessionService`] of the gRPC API, which executes the `session` and `sessions` queries of the schema through the
+/// [`GraphQLHandler`], such that calls are authenticated by their metadata, and authorized and resolved, exactly as though
+/// they were GraphQL queries
+#[derive(Clone)]
+pub struct SessionGrpc {
+    /// The handler through which the queries are executed
+    handler: GraphQLHandler<RootSchema>,
+}
+
+impl SessionGrpc {
+    /// Creates a [`SessionServiceServer`] executing queries through the handler
+    pub fn server(handler: GraphQLHandler<RootSchema>) -> SessionServiceServer<Self> {
+        SessionServiceServer::new(Self { handler })
+    }
+
+    /// Executes the query, with the variables, on behalf of the caller authenticated by the metadata of the request,
+    /// and returns its data
+    ///
+    /// The metadata is converted to the headers of the HTTP request, as `tonic` and `axum` depend upon different versions
+    /// of [`axum::http`]
+    async fn query<T>(
+        &self,
+        query: &str,
+        variables: Value,
+        request: &tonic::Request<T>,
+    ) -> Result<Value, Status> {
+        let headers = request
+            .metadata()
+            .clone()
+            .into_headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_str().as_bytes()).ok()?,
+                    HeaderValue::from_bytes(value.as_bytes()).ok()?,
+                ))
+            })
+            .collect::<HeaderMap>();
+        let response = match self.handler.execute_as(query, variables, &headers).await {
+            Ok(response) => response,
+            Err(response) => return Err(rejection(response).await),
+        };
+        if let Some(err) = response.errors.first() {
+            return Err(Status::new(code(error_status(err)), err.message.clone()));
+        }
+        response
+            .data
+            .into_json()
+            .map_err(|err| Status::internal(err.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl SessionService for SessionGrpc {
+    async fn get_session(
+        &self,
+        request: tonic::Request<GetSessionRequest>,
+    ) -> Result<tonic::Response<Session>, Status> {
+        let GetSessionRequest {
+            proposal_code,
+            proposal_number,
+            visit,
+        } = request.get_ref();
+        let variables = json!({
+            "proposalCode": proposal_code,
+            "proposalNumber": proposal_number,
+            "visit": visit,
+        });
+        let data = self.query(SESSION_QUERY, variables, &request).await?;
+        match &data["session"] {
+            Value::Null => Err(Status::not_found(format!(
+                "Session {proposal_code}{proposal_number}-{visit} does not exist"
+            ))),
+            session => Ok(tonic::Response::new(to_session(session))),
+        }
+    }
+
+    async fn list_sessions(
+        &self,
+        request: tonic::Request<ListSessionsRequest>,
+    ) -> Result<tonic::Response<ListSessionsResponse>, Status> {
+        let ListSessionsRequest {
+            proposal_code,
+            proposal_number,
+            limit,
+            offset,
+        } = request.get_ref();
+        let variables = json!({
+            "proposalCode": proposal_code,
+            "proposalNumber": proposal_number,
+            "limit": limit,
+            "offset": offset,
+        });
+        let data = self.query(SESSIONS_QUERY, variables, &request).await?;
+        let sessions = data["sessions"]
+            .as_array()
+            .map(|sessions| sessions.iter().map(to_session).collect())
+            .unwrap_or_default();
+        Ok(tonic::Response::new(ListSessionsResponse { sessions }))
+    }
+}
+
+/// Converts a session, as selected by the queries, to its message
+fn to_session(session: &Value) -> Session {
+    let proposal = &session["proposal"];
+    Session {
+        id: to_u32(&session["id"]).unwrap_or_default(),
+        visit: to_u32(&session["visit"]).unwrap_or_default(),
+        start: to_string(&session["start"]),
+        end: to_string(&session["end"]),
+        beamline: to_string(&session["beamline"]),
+        proposal: (!proposal.is_null()).then(|| Proposal {
+            code: to_string(&proposal["code"]),
+            number: to_u32(&proposal["number"]),
+            state: to_string(&proposal["state"]),
+        }),
+    }
+}
+
+/// The value as a [`u32`], if it is one
+fn to_u32(value: &Value) -> Option<u32> {
+    value.as_u64().and_then(|value| value.try_into().ok())
+}
+
+/// The value as a [`String`], if it is one
+fn to_string(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+/// The gRPC [`Code`] corresponding to the HTTP status with which the REST endpoints would respond
+fn code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+/// Converts the response with which an unauthenticated request is rejected to a [`Status`], with its body as the message
+async fn rejection(response: Response) -> Status {
+    let status = response.status();
+    Status::new(code(status), rejection_message(response).await)
+}
@@ -0,0 +1,326 @@
+use jsonwebtoken::{
+    decode, decode_header, errors::ErrorKind, jwk::JwkSet, Algorithm, DecodingKey, Validation,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use tracing::{info, instrument, warn};
+use url::Url;
+
+/// The minimum interval between refreshes of the JSON Web Key Set
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The claims of a validated JSON Web Token
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Claims {
+    /// The subject of the token
+    pub sub: Option<String>,
+    /// All other claims carried by the token
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Claims {
+    /// The Diamond federal identifier of the subject if present, otherwise the subject claim
+    pub fn subject(&self) -> Option<&str> {
+        self.other
+            .get("fedid")
+            .and_then(serde_json::Value::as_str)
+            .or(self.sub.as_deref())
+    }
+
+    /// The identifier of the OAuth2 client to which the token was issued, present in client credentials grants
+    pub fn client_id(&self) -> Option<&str> {
+        self.other
+            .get("client_id")
+            .and_then(serde_json::Value::as_str)
+    }
+}
+
+/// Decodes the [`Claims`] of a token without validating it, for informational use only
+pub fn unverified_claims(token: &str) -> Option<Claims> {
+    let mut validation = Validation::new(decode_header(token).ok()?.alg);
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    decode::<Claims>(token, &DecodingKey::from_secret(&[]), &validation)
+        .ok()
+        .map(|data| data.claims)
+}
+
+/// An error encountered whilst validating a JSON Web Token
+#[derive(Debug)]
+pub enum JwtError {
+    /// The token is malformed, expired, or otherwise fails validation
+    Invalid(jsonwebtoken::errors::Error),
+    /// The token was signed by a key absent from the key set
+    UnknownKey(Option<String>),
+    /// The key set could not be retrieved
+    KeySet(reqwest::Error),
+}
+
+impl Display for JwtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JwtError::Invalid(err) => write!(f, "Invalid token: {err}"),
+            JwtError::UnknownKey(Some(kid)) => write!(f, "Invalid token: unknown key {kid}"),
+            JwtError::UnknownKey(None) => write!(f, "Invalid token: no key identifier"),
+            JwtError::KeySet(err) => write!(f, "Could not retrieve signing keys: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
+
+impl From<jsonwebtoken::errors::Error> for JwtError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        JwtError::Invalid(err)
+    }
+}
+
+/// A cached JSON Web Key Set
+#[derive(Debug)]
+struct KeyCache {
+    /// The most recently retrieved keys
+    keys: JwkSet,
+    /// When the keys were last retrieved
+    refreshed: Option<Instant>,
+}
+
+/// Validates the signature, expiry, issuer and audience of JSON Web Tokens against a JSON Web Key Set
+#[derive(Debug)]
+pub struct JwtValidator {
+    /// A configured [`reqwest::Client`]
+    client: reqwest::Client,
+    /// The endpoint from which the key set is retrieved
+    jwks_url: Url,
+    /// The validation applied to each token, excepting the algorithm
+    validation: Validation,
+    /// The cached key set
+    cache: RwLock<KeyCache>,
+}
+
+impl JwtValidator {
+    /// Creates a [`JwtValidator`] using keys from the provided endpoint [`Url`], optionally requiring an issuer and audience
+    pub fn new(jwks_url: Url, issuer: Option<String>, audience: Vec<String>) -> Self {
+        info!("Setting up JWT validation against {jwks_url}");
+        let mut validation = Validation::default();
+        if let Some(issuer) = issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if audience.is_empty() {
+            validation.validate_aud = false;
+        } else {
+            validation.set_audience(&audience);
+        }
+        Self {
+            client: reqwest::Client::new(),
+            jwks_url,
+            validation,
+            cache: RwLock::new(KeyCache {
+                keys: JwkSet { keys: Vec::new() },
+                refreshed: None,
+            }),
+        }
+    }
+
+    /// Retrieves the key set, unless it was retrieved within the [`REFRESH_INTERVAL`]
+    #[instrument(skip(self))]
+    async fn refresh(&self) -> Result<(), JwtError> {
+        let mut cache = self.cache.write().await;
+        if cache
+            .refreshed
+            .is_some_and(|refreshed| refreshed.elapsed() < REFRESH_INTERVAL)
+        {
+            return Ok(());
+        }
+        info!("Retrieving JSON Web Key Set from {}", self.jwks_url);
+        let keys = self
+            .client
+            .get(self.jwks_url.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(JwtError::KeySet)?
+            .json::<JwkSet>()
+            .await
+            .map_err(JwtError::KeySet)?;
+        *cache = KeyCache {
+            keys,
+            refreshed: Some(Instant::now()),
+        };
+        Ok(())
+    }
+
+    /// Finds the decoding key with the provided identifier in the cached key set
+    async fn key(&self, kid: &str) -> Result<Option<DecodingKey>, JwtError> {
+        self.cache
+            .read()
+            .await
+            .keys
+            .find(kid)
+            .map(DecodingKey::from_jwk)
+            .transpose()
+            .map_err(JwtError::from)
+    }
+
+    /// Validates the token and returns its [`Claims`]
+    #[instrument(skip_all)]
+    pub async fn validate(&self, token: &str) -> Result<Claims, JwtError> {
+        let header = decode_header(token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(jsonwebtoken::errors::Error::from(ErrorKind::InvalidAlgorithm).into());
+        }
+        let kid = header.kid.ok_or(JwtError::UnknownKey(None))?;
+        let key = match self.key(&kid).await? {
+            Some(key) => key,
+            None => {
+                self.refresh().await?;
+                self.key(&kid)
+                    .await?
+                    .ok_or_else(|| JwtError::UnknownKey(Some(kid.clone())))?
+            }
+        };
+        let mut validation = self.validation.clone();
+        validation.algorithms = vec![header.alg];
+        decode::<Claims>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|err| {
+                warn!("Rejected token: {err}");
+                err.into()
+            })
+    }
+}
+
+/// Tests of the validation of tokens against a key set served locally
+#[cfg(test)]
+mod tests {
+    use super::{JwtError, JwtValidator};
+    use axum::{routing::get, Json, Router};
+    use jsonwebtoken::{
+        encode, errors::ErrorKind, get_current_timestamp, Algorithm, EncodingKey, Header,
+    };
+    use serde_json::{json, Value};
+    use std::net::{Ipv4Addr, SocketAddr};
+    use tokio::net::TcpListener;
+
+    /// The PKCS #8 DER encoded Ed25519 key with which tokens are signed
+    const SIGNING_KEY: &str = "302e020100300506032b6570042204208ffaaf2c3a33b953002ab70da6c80ace4669758b4abc2078e63aceac1e29f4a2";
+
+    /// The public half of the [`SIGNING_KEY`], as the `x` parameter of a JSON Web Key
+    const PUBLIC_KEY: &str = "aHuHHV5Bvz96u8wq0VZ-v2keYt03d7joD3y8JwI7tJg";
+
+    /// The identifier of the [`SIGNING_KEY`] in the key set
+    const KEY_ID: &str = "sessions-test";
+
+    /// Serves a key set of the [`PUBLIC_KEY`] on an ephemeral local port and returns a validator of tokens issued for the
+    /// `sessions` audience against it
+    async fn validator() -> JwtValidator {
+        let keys = json!({ "keys": [{
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "alg": "EdDSA",
+            "use": "sig",
+            "kid": KEY_ID,
+            "x": PUBLIC_KEY,
+        }] });
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .expect("Ephemeral port should be bindable");
+        let address = listener
+            .local_addr()
+            .expect("Listener should have an address");
+        let router = Router::new().route("/jwks", get(move || async move { Json(keys) }));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        JwtValidator::new(
+            format!("http://{address}/jwks").parse().unwrap(),
+            None,
+            vec!["sessions".to_string()],
+        )
+    }
+
+    /// A token of the claims, signed by the [`SIGNING_KEY`] and identifying the key
+    fn token(kid: &str, claims: Value) -> String {
+        let header = Header {
+            kid: Some(kid.to_string()),
+            ..Header::new(Algorithm::EdDSA)
+        };
+        let key = EncodingKey::from_ed_der(&hex::decode(SIGNING_KEY).unwrap());
+        encode(&header, &claims, &key).expect("Token should be signed")
+    }
+
+    /// A time relative to the current time, in seconds since the epoch
+    fn from_now(offset: i64) -> u64 {
+        get_current_timestamp().saturating_add_signed(offset)
+    }
+
+    #[tokio::test]
+    async fn valid_token_is_accepted() {
+        let token = token(
+            KEY_ID,
+            json!({ "sub": "abc12345", "aud": "sessions", "exp": from_now(3600) }),
+        );
+        let claims = validator().await.validate(&token).await.unwrap();
+        assert_eq!(claims.subject(), Some("abc12345"));
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_rejected() {
+        let token = token(
+            KEY_ID,
+            json!({ "sub": "abc12345", "aud": "sessions", "exp": from_now(-3600) }),
+        );
+        let err = validator().await.validate(&token).await.unwrap_err();
+        assert!(
+            matches!(&err, JwtError::Invalid(err) if *err.kind() == ErrorKind::ExpiredSignature),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn token_for_other_audience_is_rejected() {
+        let token = token(
+            KEY_ID,
+            json!({ "sub": "abc12345", "aud": "other", "exp": from_now(3600) }),
+        );
+        let err = validator().await.validate(&token).await.unwrap_err();
+        assert!(
+            matches!(&err, JwtError::Invalid(err) if *err.kind() == ErrorKind::InvalidAudience),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn token_of_unknown_key_is_rejected() {
+        let token = token(
+            "rotated",
+            json!({ "sub": "abc12345", "aud": "sessions", "exp": from_now(3600) }),
+        );
+        let err = validator().await.validate(&token).await.unwrap_err();
+        assert!(
+            matches!(&err, JwtError::UnknownKey(Some(kid)) if kid == "rotated"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn symmetrically_signed_token_is_rejected() {
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &json!({ "sub": "abc12345", "aud": "sessions", "exp": from_now(3600) }),
+            &EncodingKey::from_secret(PUBLIC_KEY.as_bytes()),
+        )
+        .unwrap();
+        let err = validator().await.validate(&token).await.unwrap_err();
+        assert!(
+            matches!(&err, JwtError::Invalid(err) if *err.kind() == ErrorKind::InvalidAlgorithm),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+#![forbid(unsafe_code)]
+#![doc=include_str!("../../README.md")]
+#![warn(missing_docs)]
+#![warn(clippy::missing_docs_in_private_items)]
+
+/// API key authentication for machine clients
+pub mod api_key;
+/// Metadata about the crate, courtesy of [`built`]
+mod built_info;
+/// Caches held in memory or shared in Redis
+pub mod cache;
+/// The command line interface of the service
+pub mod cli;
+/// Argument values read from, and rendered as, a configuration file
+mod config_file;
+/// Database connections with read replica load balancing
+pub mod database;
+/// Seeded data for local development
+pub mod dev;
+/// Reporting of unexpected errors to the on-call channel
+pub mod error_reporting;
+/// Rows of ISPyB tables seeded into development and test databases
+pub mod fixtures;
+/// GraphQL resolvers
+pub mod graphql;
+/// The gRPC API, serving the sessions of the GraphQL API over protobuf contracts
+pub mod grpc;
+/// JSON Web Token validation
+pub mod jwt;
+/// Listening for connections on TCP or Unix domain sockets
+mod listener;
+/// Changing the level of logs emitted at runtime
+pub mod log_level;
+/// Instruments recording the metrics of the service
+mod metrics;
+/// A mock of the Open Policy Agent for tests and local development
+pub mod mock_opa;
+/// Open Policy Agent helpers
+pub mod opa;
+/// The OpenAPI document of the routes other than GraphQL
+pub mod openapi;
+/// Metrics of the GraphQL operations executed
+pub mod operation_metrics;
+/// Spans of the GraphQL operations executed
+pub mod operation_tracing;
+/// Rate limiting of requests per client
+pub mod rate_limit;
+/// An [`axum::handler::Handler`] for GraphQL
+pub mod route_handlers;
+/// Construction of the routers serving the GraphQL API and the probes
+pub mod router;
+/// Metrics of the asynchronous runtime, which are only available when compiled with `--cfg tokio_unstable`
+#[cfg(tokio_unstable)]
+mod runtime_metrics;
+/// Restriction of the executable operations to a persisted query manifest
+pub mod safelist;
+/// Logging of resolvers which exceed a duration
+pub mod slow_resolvers;
+/// Errors which prevent the server from starting
+mod startup_error;
+/// TLS termination with certificate reloading
+mod tls;
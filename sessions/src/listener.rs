@@ -0,0 +1,140 @@
+use axum::Router;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto,
+    service::TowerToHyperService,
+};
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    io,
+    net::SocketAddr,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::{net::UnixListener, sync::watch, task::JoinSet};
+use tracing::{info, warn};
+
+/// The prefix distinguishing a Unix domain socket path from a TCP socket address
+const UNIX_PREFIX: &str = "unix:";
+
+/// An address on which connections are accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listen {
+    /// A TCP socket address
+    Tcp(SocketAddr),
+    /// The path of a Unix domain socket
+    Unix(PathBuf),
+}
+
+impl FromStr for Listen {
+    type Err = <SocketAddr as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(UNIX_PREFIX) {
+            Some(path) => Ok(Self::Unix(PathBuf::from(path))),
+            None => Ok(Self::Tcp(s.parse()?)),
+        }
+    }
+}
+
+impl Display for Listen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(socket_addr) => write!(f, "{socket_addr}"),
+            Self::Unix(path) => write!(f, "{UNIX_PREFIX}{}", path.display()),
+        }
+    }
+}
+
+/// Serves the router on a Unix domain socket at the path until the shutdown signal completes, then stops accepting
+/// connections and waits up to the shutdown timeout for in-flight requests to complete
+///
+/// Any stale socket left at the path by a previous process is replaced, and the socket is removed once serving stops, but
+/// other files at the path are left in place such that binding fails
+pub async fn serve_unix(
+    router: Router,
+    path: &Path,
+    shutdown_signal: impl Future<Output = ()>,
+    shutdown_timeout: Duration,
+) -> io::Result<()> {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.file_type().is_socket() => tokio::fs::remove_file(path).await?,
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    let (draining, drain) = watch::channel(());
+    let mut connections = JoinSet::new();
+    tokio::pin!(shutdown_signal);
+    loop {
+        let socket = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((socket, _)) => socket,
+                Err(err) => {
+                    warn!("Could not accept connection: {err}");
+                    continue;
+                }
+            },
+            Some(_) = connections.join_next() => continue,
+            () = &mut shutdown_signal => break,
+        };
+        let service = TowerToHyperService::new(router.clone());
+        let mut drain = drain.clone();
+        connections.spawn(async move {
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(socket), service);
+            tokio::pin!(connection);
+            let result = tokio::select! {
+                result = connection.as_mut() => result,
+                _ = drain.changed() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(err) = result {
+                warn!("Connection failed: {err}");
+            }
+        });
+    }
+    drop(listener);
+    info!("Shutting down, waiting up to {shutdown_timeout:?} for in-flight requests");
+    draining.send_replace(());
+    let drained = tokio::time::timeout(shutdown_timeout, async {
+        while connections.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        warn!("In-flight requests did not complete within {shutdown_timeout:?}");
+    }
+    tokio::fs::remove_file(path).await
+}
+
+/// Properties of the parsing and formatting of listen addresses, which are given as arguments and shown in logs
+#[cfg(test)]
+mod tests {
+    use super::Listen;
+    use proptest::prelude::*;
+    use std::net::{IpAddr, SocketAddr};
+
+    proptest! {
+        #[test]
+        fn tcp_address_round_trips(ip: IpAddr, port: u16) {
+            let listen = Listen::Tcp(SocketAddr::new(ip, port));
+            prop_assert_eq!(listen.to_string().parse::<Listen>(), Ok(listen));
+        }
+
+        #[test]
+        fn unix_path_round_trips(path: String) {
+            let listen = Listen::Unix(path.into());
+            prop_assert_eq!(listen.to_string().parse::<Listen>(), Ok(listen));
+        }
+
+        #[test]
+        fn arbitrary_address_does_not_panic(address: String) {
+            address.parse::<Listen>().ok();
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::opa::AUDIT_TARGET;
+use tracing::{info, level_filters::LevelFilter, warn, Level};
+use tracing_subscriber::{filter::Targets, reload, Registry};
+
+/// A handle on the level at which logs are emitted, such that it may be changed whilst the service is running without losing
+/// the state of a misbehaving replica to a restart
+#[derive(Debug, Clone)]
+pub struct LogLevel {
+    /// The handle by which the filter of the subscriber is replaced
+    handle: reload::Handle<Targets, Registry>,
+    /// The level configured at startup
+    initial: Level,
+}
+
+impl LogLevel {
+    /// Creates a reloadable filter emitting logs at the level, and authorization audit records regardless of it, along with a
+    /// [`LogLevel`] by which it is changed
+    pub fn new(level: Level) -> (reload::Layer<Targets, Registry>, Self) {
+        let (filter, handle) = reload::Layer::new(filter(level));
+        (
+            filter,
+            Self {
+                handle,
+                initial: level,
+            },
+        )
+    }
+
+    /// The level at which logs are currently emitted
+    pub fn current(&self) -> Result<LevelFilter, reload::Error> {
+        self.handle
+            .with_current(|targets| targets.default_level().unwrap_or(LevelFilter::OFF))
+    }
+
+    /// Emits logs at the level from now on
+    pub fn set(&self, level: Level) -> Result<(), reload::Error> {
+        self.handle.reload(filter(level))?;
+        info!("Log level set to {level}");
+        Ok(())
+    }
+
+    /// Toggles between the level configured at startup and [`Level::DEBUG`] each time a hangup signal is received
+    #[cfg(unix)]
+    pub async fn toggle_on_hangup(self) {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(hangup) => hangup,
+            Err(err) => {
+                warn!("Could not listen for hangup signal: {err}");
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            let level = match self.current() {
+                Ok(current) if current == LevelFilter::from_level(self.initial) => Level::DEBUG,
+                _ => self.initial,
+            };
+            if let Err(err) = self.set(level) {
+                warn!("Could not set log level: {err}");
+            }
+        }
+    }
+}
+
+/// A filter emitting logs at the level, and authorization audit records regardless of it
+fn filter(level: Level) -> Targets {
+    Targets::new()
+        .with_default(level)
+        .with_target(AUDIT_TARGET, Level::INFO)
+}
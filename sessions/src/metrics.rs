@@ -0,0 +1,122 @@
+use opentelemetry::metrics::{Counter, Histogram, Meter, UpDownCounter};
+use std::sync::OnceLock;
+
+/// The instruments by which the service records its metrics, created from the global meter provider on first use
+///
+/// Metrics are recorded directly, rather than as events, such that they are unaffected by the log level
+#[derive(Debug)]
+pub struct Metrics {
+    /// The duration of each HTTP request, by method, route and response status
+    pub http_server_request_duration_seconds: Histogram<f64>,
+    /// The number of requests shed as too many were in flight
+    pub shed_requests: Counter<u64>,
+    /// The number of requests which did not complete within the request timeout
+    pub request_timeouts: Counter<u64>,
+    /// The number of requests rejected as their client exceeded its rate
+    pub rate_limited_requests: Counter<u64>,
+    /// The duration of each GraphQL operation, by operation name and type
+    pub graphql_operation_duration_seconds: Histogram<f64>,
+    /// The number of GraphQL operations executed, by operation name and type
+    pub graphql_operations: Counter<u64>,
+    /// The number of errors in the responses to GraphQL operations, by operation name and type
+    pub graphql_errors: Counter<u64>,
+    /// The number of decisions made by OPA, by policy and outcome
+    pub opa_decisions: Counter<u64>,
+    /// Whether the OPA circuit breaker is open
+    pub opa_circuit_breaker_open: UpDownCounter<i64>,
+    /// The number of times the OPA circuit breaker has opened
+    pub opa_circuit_breaker_trips: Counter<u64>,
+    /// The number of times the decision cache has been cleared as the OPA bundles changed
+    pub opa_decision_cache_invalidations: Counter<u64>,
+    /// The duration of each request to OPA, by policy and status
+    pub opa_request_duration_seconds: Histogram<f64>,
+    /// The time taken to acquire a connection from the pool of each database
+    pub db_pool_acquire_wait_seconds: Histogram<f64>,
+    /// The number of times a connection could not be acquired from the pool of a database within its timeout
+    pub db_pool_acquire_timeouts: Counter<u64>,
+    /// The number of database queries retried after a transient failure
+    pub database_query_retries: Counter<u64>,
+}
+
+impl Metrics {
+    /// The instruments of the service, created on first use such that they are recorded by the meter provider set when the
+    /// telemetry is set up
+    pub fn get() -> &'static Self {
+        /// The instruments, once created
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS
+            .get_or_init(|| Self::new(&opentelemetry::global::meter(crate::built_info::PKG_NAME)))
+    }
+
+    /// Creates the instruments of the service with the [`Meter`]
+    fn new(meter: &Meter) -> Self {
+        Self {
+            http_server_request_duration_seconds: meter
+                .f64_histogram("http_server_request_duration_seconds")
+                .with_description("The duration of each HTTP request")
+                .init(),
+            shed_requests: meter
+                .u64_counter("shed_requests")
+                .with_description("The number of requests shed as too many were in flight")
+                .init(),
+            request_timeouts: meter
+                .u64_counter("request_timeouts")
+                .with_description(
+                    "The number of requests which did not complete within the timeout",
+                )
+                .init(),
+            rate_limited_requests: meter
+                .u64_counter("rate_limited_requests")
+                .with_description(
+                    "The number of requests rejected as their client exceeded its rate",
+                )
+                .init(),
+            graphql_operation_duration_seconds: meter
+                .f64_histogram("graphql_operation_duration_seconds")
+                .with_description("The duration of each GraphQL operation")
+                .init(),
+            graphql_operations: meter
+                .u64_counter("graphql_operations")
+                .with_description("The number of GraphQL operations executed")
+                .init(),
+            graphql_errors: meter
+                .u64_counter("graphql_errors")
+                .with_description("The number of errors in the responses to GraphQL operations")
+                .init(),
+            opa_decisions: meter
+                .u64_counter("opa_decisions")
+                .with_description("The number of decisions made by OPA")
+                .init(),
+            opa_circuit_breaker_open: meter
+                .i64_up_down_counter("opa_circuit_breaker_open")
+                .with_description("Whether the OPA circuit breaker is open")
+                .init(),
+            opa_circuit_breaker_trips: meter
+                .u64_counter("opa_circuit_breaker_trips")
+                .with_description("The number of times the OPA circuit breaker has opened")
+                .init(),
+            opa_decision_cache_invalidations: meter
+                .u64_counter("opa_decision_cache_invalidations")
+                .with_description("The number of times the decision cache has been cleared")
+                .init(),
+            opa_request_duration_seconds: meter
+                .f64_histogram("opa_request_duration_seconds")
+                .with_description("The duration of each request to OPA")
+                .init(),
+            db_pool_acquire_wait_seconds: meter
+                .f64_histogram("db_pool_acquire_wait_seconds")
+                .with_description("The time taken to acquire a connection from the pool")
+                .init(),
+            db_pool_acquire_timeouts: meter
+                .u64_counter("db_pool_acquire_timeouts")
+                .with_description("The number of times a connection could not be acquired in time")
+                .init(),
+            database_query_retries: meter
+                .u64_counter("database_query_retries")
+                .with_description(
+                    "The number of database queries retried after a transient failure",
+                )
+                .init(),
+        }
+    }
+}
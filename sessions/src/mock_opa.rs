@@ -0,0 +1,177 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{Method, Uri},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    fs::File,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::Path,
+    sync::Arc,
+};
+use tokio::net::TcpListener;
+use tracing::info;
+use url::Url;
+
+/// The policy, relative to the OPA Data API, of the default decision made on requests to the root of the endpoint
+const DEFAULT_POLICY: &str = "system/main";
+
+/// The policy, relative to the OPA Data API, of the batch decision
+const BATCH_POLICY: &str = "batch/main";
+
+/// A residual policy which cannot be translated to SQL, such that callers of the Compile API fall back to deciding each
+/// input in a batch when the decision depends upon unknown parameters
+const UNTRANSLATABLE_RESIDUAL: &str =
+    r#"[[{"terms": {"type": "ref", "value": [{"type": "var", "value": "mock"}]}}]]"#;
+
+/// A rule of the [`MockOpa`], deciding the inputs which match it
+#[derive(Debug, Clone, Deserialize)]
+struct MockRule {
+    /// The policy, relative to the OPA Data API such as `sessions/read`, to which the rule applies, or every policy if unset
+    #[serde(default)]
+    policy: Option<String>,
+    /// A portion of the input document, which matches every input containing the same values at the same paths
+    #[serde(default = "empty_object")]
+    input: Value,
+    /// Whether matching inputs are permitted
+    allow: bool,
+    /// A summary of why matching inputs are denied
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl MockRule {
+    /// Whether the rule applies to decisions of the policy
+    fn applies_to(&self, policy: &str) -> bool {
+        self.policy.iter().all(|rule| rule == policy)
+    }
+
+    /// Whether the rule applies to the input of the policy
+    fn matches(&self, policy: &str, input: &Value) -> bool {
+        self.applies_to(policy) && contains(input, &self.input)
+    }
+
+    /// The decision of the rule, as would be produced by the policy
+    fn decision(&self) -> Value {
+        json!({ "allow": self.allow, "reason": self.reason })
+    }
+}
+
+/// An empty JSON object, which is contained by every input
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+/// Whether the value contains the pattern, having the same values at each of its paths
+fn contains(value: &Value, pattern: &Value) -> bool {
+    match (value, pattern) {
+        (Value::Object(value), Value::Object(pattern)) => pattern
+            .iter()
+            .all(|(key, pattern)| value.get(key).is_some_and(|value| contains(value, pattern))),
+        (value, pattern) => value == pattern,
+    }
+}
+
+/// A mock of the Open Policy Agent, deciding each input by the first of its `MockRule`s which matches and permitting
+/// those which match none, such that the service may be authorized without OPA or the policies it is deployed with
+///
+/// Listings, whether partially evaluated or decided in batches, are decided by the rules of the `batch/main` policy and
+/// those of every policy
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct MockOpa {
+    /// The rules, in the order in which they are tried
+    rules: Vec<MockRule>,
+}
+
+impl MockOpa {
+    /// Loads the `MockRule`s from a YAML file of their list
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        Ok(serde_yaml::from_reader(File::open(path)?)?)
+    }
+
+    /// Decides the input of the policy by the first matching rule, permitting it if none match
+    fn decide(&self, policy: &str, input: &Value) -> Value {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(policy, input))
+            .map_or_else(|| json!({ "allow": true }), MockRule::decision)
+    }
+
+    /// Decides each of the parameters of a batch input as though it were the sole parameters of the input
+    fn decide_batch(&self, input: &Value) -> Vec<bool> {
+        let parameters = input["parameters"].as_array().cloned().unwrap_or_default();
+        parameters
+            .into_iter()
+            .map(|parameters| {
+                let mut input = input.clone();
+                input["parameters"] = parameters;
+                self.decide(BATCH_POLICY, &input)["allow"] == true
+            })
+            .collect()
+    }
+
+    /// Partially evaluates the decision on the input, whose parameters are unknown, returning an unconditional residual
+    /// if the first rule matching the known portion does not depend upon the parameters, or one which cannot be
+    /// translated otherwise
+    fn compile(&self, input: &Value) -> Value {
+        let rule = self.rules.iter().find(|rule| {
+            let mut known = rule.input.clone();
+            if let Value::Object(known) = &mut known {
+                known.remove("parameters");
+            }
+            rule.applies_to(BATCH_POLICY) && contains(input, &known)
+        });
+        match rule {
+            Some(rule) if rule.input.get("parameters").is_some() => {
+                serde_json::from_str(UNTRANSLATABLE_RESIDUAL)
+                    .expect("Untranslatable residual should be valid JSON")
+            }
+            Some(MockRule { allow: false, .. }) => json!([]),
+            _ => json!([[]]),
+        }
+    }
+
+    /// Serves the mock on an ephemeral local port and returns its [`Url`]
+    pub async fn serve(self) -> Result<Url, std::io::Error> {
+        let listener =
+            TcpListener::bind(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))).await?;
+        let url = Url::parse(&format!("http://{}/", listener.local_addr()?))
+            .expect("Socket addresses should form valid URLs");
+        info!(
+            "Serving mock Open Policy Agent with {} rules at {url}",
+            self.rules.len()
+        );
+        let router = Router::new().fallback(respond).with_state(Arc::new(self));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Ok(url)
+    }
+}
+
+/// Responds to Open Policy Agent requests with the decisions of the [`MockOpa`]
+async fn respond(
+    State(mock): State<Arc<MockOpa>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Json<Value> {
+    let body = serde_json::from_slice::<Value>(&body).unwrap_or_default();
+    Json(match (method, uri.path()) {
+        (Method::POST, "/") => mock.decide(DEFAULT_POLICY, &body),
+        (Method::POST, "/v1/compile") => {
+            json!({ "result": { "queries": mock.compile(&body["input"]) } })
+        }
+        (Method::POST, "/v1/data/batch/main") => {
+            json!({ "result": { "allowed": mock.decide_batch(&body["input"]) } })
+        }
+        (Method::POST, path) if path.starts_with("/v1/data/") => {
+            let policy = path.trim_start_matches("/v1/data/");
+            json!({ "result": mock.decide(policy, &body["input"]) })
+        }
+        (_, path) if path.starts_with("/v1/data") => json!({ "result": {} }),
+        _ => json!({}),
+    })
+}
@@ -1,82 +1,1658 @@
+use crate::{
+    api_key::ServiceIdentity,
+    cache::{digest_key, Cache},
+    jwt::{unverified_claims, Claims},
+    metrics::Metrics,
+};
+use async_graphql::{parser::types::OperationType, ErrorExtensions, Guard, ResultExt};
 use axum_extra::headers::{authorization::Bearer, Authorization};
+use opentelemetry::baggage::BaggageExt;
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    Condition, Value,
+};
 use serde::{Deserialize, Serialize};
-use tracing::{info, instrument};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    future::Future,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::OnceCell, time::Instant};
+use tracing::{info, instrument, warn};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use url::Url;
 
 /// Parametrers required by OPA to make the policy decision
 #[derive(Debug, Serialize)]
 pub struct OpaInput<P: Serialize> {
-    /// The access Json Web Token (JWT) associated with the request
+    /// The access Json Web Token (JWT) associated with the request, omitted if the claims are forwarded
     pub token: Option<String>,
+    /// The claims of the validated JWT, if forwarded in place of the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Claims>,
+    /// The identity of the machine client making the request, if authenticated by API key or service token,
+    /// acting on behalf of the user identified by the token or claims if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
     /// Additional parameters required by OPA
     pub parameters: P,
+    /// Information about the operation being authorized, which is not sent to OPA
+    #[serde(skip)]
+    pub context: DecisionContext,
+    /// The decisions already made whilst handling the request, if memoised
+    #[serde(skip)]
+    pub memo: Option<DecisionMemo>,
 }
 
 impl<P: Serialize> OpaInput<P> {
     /// Create an [`OpaInput`] from an [`async_graphql::Context`] and some requisite parameters
-    pub fn new(ctx: &async_graphql::Context, parameters: P) -> Result<Self, async_graphql::Error> {
-        Ok(Self {
-            token: ctx
-                .data::<Option<Authorization<Bearer>>>()?
-                .as_ref()
-                .map(|header| header.token().to_string()),
+    pub fn new(ctx: &async_graphql::Context, parameters: P) -> Self {
+        let (token, claims) = request_credentials(ctx);
+        Self {
+            token,
+            claims,
+            service: request_service(ctx),
             parameters,
-        })
+            context: DecisionContext::new(ctx),
+            memo: ctx.data_opt::<DecisionMemo>().cloned(),
+        }
     }
 }
 
+/// The known portion of the OPA input used for partial evaluation, the parameters are left unknown
+#[derive(Debug, Serialize)]
+pub struct OpaPartialInput {
+    /// The access Json Web Token (JWT) associated with the request, omitted if the claims are forwarded
+    pub token: Option<String>,
+    /// The claims of the validated JWT, if forwarded in place of the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Claims>,
+    /// The identity of the machine client making the request, if authenticated by API key or service token,
+    /// acting on behalf of the user identified by the token or claims if present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// Information about the operation being authorized, which is not sent to OPA
+    #[serde(skip)]
+    pub context: DecisionContext,
+}
+
+impl OpaPartialInput {
+    /// Create an [`OpaPartialInput`] from an [`async_graphql::Context`]
+    pub fn new(ctx: &async_graphql::Context) -> Self {
+        let (token, claims) = request_credentials(ctx);
+        Self {
+            token,
+            claims,
+            service: request_service(ctx),
+            context: DecisionContext::new(ctx),
+        }
+    }
+}
+
+/// Information about the operation being authorized, used to degrade gracefully and for auditing
+#[derive(Debug, Clone)]
+pub struct DecisionContext {
+    /// Whether the operation is a read-only query
+    pub read_only: bool,
+    /// The identity on whose behalf the operation is performed, if known
+    pub subject: Option<String>,
+    /// The identity of the machine client performing the operation, if any
+    pub service: Option<String>,
+    /// The name of the field being resolved
+    pub operation: String,
+    /// The proposal the field being resolved concerns, if given as an argument
+    pub proposal: Option<String>,
+}
+
+impl DecisionContext {
+    /// Create a [`DecisionContext`] from an [`async_graphql::Context`]
+    fn new(ctx: &async_graphql::Context) -> Self {
+        Self {
+            read_only: is_read_only(ctx),
+            subject: request_subject(ctx),
+            service: request_service(ctx),
+            operation: ctx.item.node.name.node.to_string(),
+            proposal: argument_proposal(ctx),
+        }
+    }
+
+    /// The OpenTelemetry baggage identifying the pseudonymised subject and the proposal, by which traces may be segmented
+    /// without revealing the identity of the user
+    fn baggage(&self) -> Vec<opentelemetry::KeyValue> {
+        self.subject
+            .as_ref()
+            .map(|subject| {
+                opentelemetry::KeyValue::new(
+                    "enduser.pseudo.id",
+                    hex::encode(&Sha256::digest(subject.as_bytes())[..8]),
+                )
+            })
+            .into_iter()
+            .chain(
+                self.proposal
+                    .clone()
+                    .map(|proposal| opentelemetry::KeyValue::new("proposal", proposal)),
+            )
+            .collect()
+    }
+}
+
+/// The proposal named by the `proposalCode` and `proposalNumber` arguments of the field being resolved, e.g. `cm12345`, or
+/// only its number if no code is given
+fn argument_proposal(ctx: &async_graphql::Context) -> Option<String> {
+    let arguments = ctx.field().arguments().ok()?;
+    let argument = |name: &str| {
+        arguments
+            .iter()
+            .find(|(argument, _)| argument.as_str() == name)
+            .map(|(_, value)| value)
+    };
+    let number = match argument("proposalNumber")? {
+        async_graphql::Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+    match argument("proposalCode") {
+        Some(async_graphql::Value::String(code)) => Some(format!("{code}{number}")),
+        _ => Some(number),
+    }
+}
+
+/// The identity on whose behalf the request is made, preferring the claims of a validated token, then
+/// the claims of an unverified token, then the identity of a machine client acting on its own behalf
+pub fn request_subject(ctx: &async_graphql::Context) -> Option<String> {
+    let claims = match ctx.data_opt::<Option<Claims>>() {
+        Some(Some(claims)) => Some(claims.clone()),
+        _ => ctx
+            .data_opt::<Option<Authorization<Bearer>>>()
+            .and_then(Option::as_ref)
+            .and_then(|header| unverified_claims(header.token())),
+    };
+    claims
+        .as_ref()
+        .and_then(Claims::subject)
+        .map(str::to_string)
+        .or_else(|| request_service(ctx))
+}
+
+/// Retrieves the machine client identity of the request from the [`async_graphql::Context`]
+fn request_service(ctx: &async_graphql::Context) -> Option<String> {
+    ctx.data_opt::<Option<ServiceIdentity>>()
+        .and_then(Option::as_ref)
+        .map(|service| service.0.clone())
+}
+
+/// Whether the operation being executed is a query, and so cannot modify data
+fn is_read_only(ctx: &async_graphql::Context) -> bool {
+    ctx.query_env.operation.node.ty == OperationType::Query
+}
+
+/// Whether the claims of validated tokens are sent to OPA in place of the tokens themselves
+#[derive(Debug, Clone, Copy)]
+pub struct ForwardClaims(pub bool);
+
+/// Retrieves the credentials sent to OPA from the [`async_graphql::Context`], being either the
+/// bearer token or, if [`ForwardClaims`] is set and the token was validated, its [`Claims`]
+fn request_credentials(ctx: &async_graphql::Context) -> (Option<String>, Option<Claims>) {
+    let forward = ctx
+        .data_opt::<ForwardClaims>()
+        .is_some_and(|forward| forward.0);
+    match ctx.data_opt::<Option<Claims>>() {
+        Some(Some(claims)) if forward => (None, Some(claims.clone())),
+        _ => (request_token(ctx), None),
+    }
+}
+
+/// Retrieves the bearer token of the request from the [`async_graphql::Context`]
+fn request_token(ctx: &async_graphql::Context) -> Option<String> {
+    ctx.data_opt::<Option<Authorization<Bearer>>>()
+        .and_then(Option::as_ref)
+        .map(|header| header.token().to_string())
+}
+
 /// The policy decision made by opa
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Decision {
     /// Whether the operation should be permitted
     pub allow: bool,
+    /// A summary of why the operation was denied, if provided by the policy
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// The individual policy violations which caused the operation to be denied, if provided by the policy
+    #[serde(default)]
+    pub violations: Vec<String>,
 }
 
-/// An Open Policy Agent client
+/// The [`Decision`]s made whilst handling a single request, such that each unique decision is made at most once
+#[derive(Debug, Clone, Default)]
+pub struct DecisionMemo(Arc<Mutex<HashMap<String, Arc<OnceCell<Decision>>>>>);
+
+impl DecisionMemo {
+    /// Retrieves the cell holding the [`Decision`] for the key, which is empty if it has not yet been made
+    fn cell(&self, key: &str) -> Arc<OnceCell<Decision>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+}
+
+/// The key identifying a decision of the policy at the path, or of the default decision if [`None`], on the input
+fn decision_key(policy: Option<&str>, input: &impl Serialize) -> Option<String> {
+    digest_key(&(policy.unwrap_or_default(), input)).ok()
+}
+
+/// The maximum number of characters of each denial reason exposed to users
+const MAX_REASON_LENGTH: usize = 256;
+
+/// The maximum number of policy violations exposed to users
+const MAX_VIOLATIONS: usize = 16;
+
+/// Strips control characters from a denial reason and truncates it to [`MAX_REASON_LENGTH`]
+fn sanitise_reason(reason: &str) -> String {
+    reason
+        .chars()
+        .filter(|character| !character.is_control())
+        .take(MAX_REASON_LENGTH)
+        .collect()
+}
+
+/// The OPA Data API path of the batch decision
+const BATCH_PATH: &str = "v1/data/batch/main";
+
+/// The policy of the batch decision, by which its metrics are labelled
+const BATCH_POLICY: &str = "batch/main";
+
+/// The policy of the default decision, made on requests to the root of the endpoint, by which its metrics are labelled
+const DEFAULT_POLICY: &str = "system/main";
+
+/// Records the number of decisions made by the policy with the outcome
+fn record_decisions(policy: &str, outcome: &'static str, count: u64) {
+    if count > 0 {
+        Metrics::get().opa_decisions.add(
+            count,
+            &[
+                opentelemetry::KeyValue::new("policy", policy.to_string()),
+                opentelemetry::KeyValue::new("outcome", outcome),
+            ],
+        );
+    }
+}
+
+/// A request to the OPA Data API
+#[derive(Debug, Serialize)]
+struct DataRequest<I: Serialize> {
+    /// The input document
+    input: I,
+}
+
+/// A response from the OPA Data API
+#[derive(Debug, Deserialize)]
+struct DataResponse<R> {
+    /// The value of the requested document, absent if undefined
+    result: Option<R>,
+}
+
+/// The policy decisions made by opa for a batch of inputs
+#[derive(Debug, Deserialize)]
+pub struct BatchDecision {
+    /// Whether each operation should be permitted, in the order requested
+    pub allowed: Vec<bool>,
+}
+
+/// The query partially evaluated by the OPA Compile API
+const COMPILE_QUERY: &str = "data.system.main.allow == true";
+
+/// The portion of the input left unknown during partial evaluation
+const COMPILE_UNKNOWNS: &[&str] = &["input.parameters"];
+
+/// A request to the OPA Compile API
+#[derive(Debug, Serialize)]
+struct CompileRequest<'a> {
+    /// The query to be partially evaluated
+    query: &'a str,
+    /// The known portion of the input
+    input: OpaPartialInput,
+    /// References which should be treated as unknown
+    unknowns: &'a [&'a str],
+}
+
+/// A response from the OPA Compile API
+#[derive(Debug, Deserialize)]
+struct CompileResponse {
+    /// The result of partial evaluation
+    result: PartialDecision,
+}
+
+/// The residual policy produced by partial evaluation
+///
+/// This is a disjunction of queries, each of which is a conjunction of expressions. No queries
+/// means the policy can never be satisfied, whilst an empty query is unconditionally satisfied.
+#[derive(Debug, Deserialize)]
+pub struct PartialDecision {
+    /// The residual queries, any of which permits access
+    #[serde(default)]
+    queries: Vec<Vec<Expression>>,
+}
+
+/// A single expression within a residual query
+#[derive(Debug, Deserialize)]
+struct Expression {
+    /// Whether the expression is negated
+    #[serde(default)]
+    negated: bool,
+    /// The terms of the expression, a call is a list with the operator first
+    terms: Terms,
+}
+
+/// The terms of a residual expression
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Terms {
+    /// An operator applied to some operands
+    Call(Vec<Term>),
+    /// A lone term, which must be truthy
+    Single(Term),
+}
+
+/// A term within a residual expression
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+enum Term {
+    /// A null literal
+    Null,
+    /// A boolean literal
+    Boolean(bool),
+    /// A numeric literal
+    Number(serde_json::Number),
+    /// A string literal
+    String(String),
+    /// A variable
+    Var(String),
+    /// A reference, such as `input.parameters.proposal`
+    Ref(Vec<Term>),
+}
+
+impl Term {
+    /// The name of the input parameter referenced by this term, if any
+    fn parameter(&self) -> Option<&str> {
+        match self {
+            Term::Ref(path) => match path.as_slice() {
+                [Term::Var(input), Term::String(parameters), Term::String(name)]
+                    if input == "input" && parameters == "parameters" =>
+                {
+                    Some(name)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The SQL value of a literal term, rendered as a string if the column is textual, if any
+    fn value(&self, column: &ParameterColumn) -> Option<Value> {
+        if let ParameterColumn::Text(_) = column {
+            return match self {
+                Term::Boolean(value) => Some(value.to_string().into()),
+                Term::Number(number) => Some(number.to_string().into()),
+                Term::String(value) => Some(value.clone().into()),
+                Term::Null | Term::Var(_) | Term::Ref(_) => None,
+            };
+        }
+        match self {
+            Term::Boolean(value) => Some((*value).into()),
+            Term::Number(number) => number
+                .as_u64()
+                .map(Value::from)
+                .or_else(|| number.as_i64().map(Value::from))
+                .or_else(|| number.as_f64().map(Value::from)),
+            Term::String(value) => Some(value.clone().into()),
+            Term::Null | Term::Var(_) | Term::Ref(_) => None,
+        }
+    }
+
+    /// The comparison operator named by this term, if any
+    fn operator(&self) -> Option<Comparison> {
+        let Term::Ref(path) = self else { return None };
+        let [Term::Var(name)] = path.as_slice() else {
+            return None;
+        };
+        match name.as_str() {
+            "eq" | "equal" => Some(Comparison::Equal),
+            "neq" => Some(Comparison::NotEqual),
+            "lt" => Some(Comparison::Less),
+            "lte" => Some(Comparison::LessOrEqual),
+            "gt" => Some(Comparison::Greater),
+            "gte" => Some(Comparison::GreaterOrEqual),
+            _ => None,
+        }
+    }
+}
+
+/// The column of an input parameter of a residual policy
+#[derive(Debug)]
+pub enum ParameterColumn {
+    /// A column of the same type as the parameter
+    Native(Expr),
+    /// A textual column holding the parameter rendered as a string, which may only be compared for equality
+    Text(Expr),
+}
+
+/// A comparison operator which can be translated to SQL
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    /// Both operands are equal
+    Equal,
+    /// The operands differ
+    NotEqual,
+    /// The left operand is strictly less than the right
+    Less,
+    /// The left operand is less than or equal to the right
+    LessOrEqual,
+    /// The left operand is strictly greater than the right
+    Greater,
+    /// The left operand is greater than or equal to the right
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// The equivalent comparison with the operands swapped
+    fn flip(self) -> Self {
+        match self {
+            Comparison::Equal => Comparison::Equal,
+            Comparison::NotEqual => Comparison::NotEqual,
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Greater => Comparison::Less,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+        }
+    }
+
+    /// Applies the comparison between a column and a value
+    fn apply(self, column: Expr, value: Value) -> SimpleExpr {
+        match self {
+            Comparison::Equal => column.eq(value),
+            Comparison::NotEqual => column.ne(value),
+            Comparison::Less => column.lt(value),
+            Comparison::LessOrEqual => column.lte(value),
+            Comparison::Greater => column.gt(value),
+            Comparison::GreaterOrEqual => column.gte(value),
+        }
+    }
+}
+
+impl Expression {
+    /// Translates the expression into a [`Condition`], using `column` to resolve input parameters
+    fn to_condition(
+        &self,
+        column: &impl Fn(&str) -> Option<ParameterColumn>,
+    ) -> Result<Condition, anyhow::Error> {
+        let (operator, parameter, value) = match &self.terms {
+            Terms::Single(term) => {
+                let parameter = term
+                    .parameter()
+                    .ok_or(anyhow::anyhow!("Unsupported residual policy expression"))?;
+                (Comparison::Equal, parameter, &Term::Boolean(true))
+            }
+            Terms::Call(terms) => {
+                let [operator, left, right] = terms.as_slice() else {
+                    return Err(anyhow::anyhow!("Unsupported residual policy expression"));
+                };
+                let operator = operator
+                    .operator()
+                    .ok_or(anyhow::anyhow!("Unsupported residual policy operator"))?;
+                match (left.parameter(), right.parameter()) {
+                    (Some(parameter), None) => (operator, parameter, right),
+                    (None, Some(parameter)) => (operator.flip(), parameter, left),
+                    _ => return Err(anyhow::anyhow!("Unsupported residual policy expression")),
+                }
+            }
+        };
+        let column = column(parameter).ok_or(anyhow::anyhow!(
+            "Unsupported residual policy parameter: {parameter}"
+        ))?;
+        let value = value
+            .value(&column)
+            .ok_or(anyhow::anyhow!("Unsupported residual policy value"))?;
+        let expression = match (column, operator) {
+            (ParameterColumn::Native(column), operator)
+            | (
+                ParameterColumn::Text(column),
+                operator @ (Comparison::Equal | Comparison::NotEqual),
+            ) => operator.apply(column, value),
+            (ParameterColumn::Text(_), _) => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported residual policy comparison of textual parameter: {parameter}"
+                ))
+            }
+        };
+        let condition = Condition::all().add(expression);
+        Ok(if self.negated {
+            condition.not()
+        } else {
+            condition
+        })
+    }
+}
+
+impl PartialDecision {
+    /// Translates the residual policy into a [`Condition`], using `column` to resolve input
+    /// parameters, or [`None`] if access can never be permitted
+    pub fn into_condition(
+        self,
+        column: impl Fn(&str) -> Option<ParameterColumn>,
+    ) -> Result<Option<Condition>, anyhow::Error> {
+        if self.queries.is_empty() {
+            return Ok(None);
+        }
+        self.queries
+            .iter()
+            .try_fold(Condition::any(), |any, query| {
+                let all = query.iter().try_fold(Condition::all(), |all, expression| {
+                    Ok::<_, anyhow::Error>(all.add(expression.to_condition(&column)?))
+                })?;
+                Ok(any.add(all))
+            })
+            .map(Some)
+    }
+}
+
+/// An error encountered whilst obtaining a policy decision from OPA
+#[derive(Debug)]
+pub enum OpaError {
+    /// OPA did not respond within the configured timeout
+    Timeout(reqwest::Error),
+    /// The request to OPA failed
+    Request(reqwest::Error),
+    /// The OPA endpoint could not be constructed
+    Endpoint(url::ParseError),
+    /// OPA responded with a decision which could not be interpreted
+    InvalidDecision(String),
+    /// The policy denied access
+    Denied {
+        /// A sanitised summary of why access was denied, if provided by the policy
+        reason: Option<String>,
+        /// The sanitised policy violations which caused access to be denied
+        violations: Vec<String>,
+    },
+    /// Requests to OPA are suspended as it appears to be unavailable
+    CircuitOpen,
+    /// A required policy has not been loaded by OPA
+    PolicyNotLoaded(String),
+}
+
+impl OpaError {
+    /// A machine readable code identifying the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            OpaError::Timeout(_) => "OPA_TIMEOUT",
+            OpaError::Request(_) => "OPA_UNAVAILABLE",
+            OpaError::Endpoint(_) => "OPA_MISCONFIGURED",
+            OpaError::InvalidDecision(_) => "OPA_INVALID_DECISION",
+            OpaError::Denied { .. } => "FORBIDDEN",
+            OpaError::CircuitOpen => "OPA_CIRCUIT_OPEN",
+            OpaError::PolicyNotLoaded(_) => "OPA_POLICY_NOT_LOADED",
+        }
+    }
+
+    /// Whether the error indicates OPA is unavailable, rather than a problem with the request
+    fn is_outage(&self) -> bool {
+        match self {
+            OpaError::Timeout(err) | OpaError::Request(err) => is_transient(err),
+            OpaError::Endpoint(_)
+            | OpaError::InvalidDecision(_)
+            | OpaError::Denied { .. }
+            | OpaError::CircuitOpen
+            | OpaError::PolicyNotLoaded(_) => false,
+        }
+    }
+}
+
+impl Display for OpaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpaError::Timeout(err) => write!(f, "Policy decision timed out: {err}"),
+            OpaError::Request(err) => write!(f, "Policy decision failed: {err}"),
+            OpaError::Endpoint(err) => write!(f, "Invalid policy endpoint: {err}"),
+            OpaError::InvalidDecision(reason) => write!(f, "Invalid policy decision: {reason}"),
+            OpaError::Denied {
+                reason: Some(reason),
+                ..
+            } => write!(f, "Access denied: {reason}"),
+            OpaError::Denied { reason: None, .. } => write!(f, "Access denied"),
+            OpaError::CircuitOpen => write!(f, "Policy decisions are temporarily unavailable"),
+            OpaError::PolicyNotLoaded(policy) => write!(f, "Policy {policy} is not loaded"),
+        }
+    }
+}
+
+impl std::error::Error for OpaError {}
+
+impl From<reqwest::Error> for OpaError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            OpaError::Timeout(err)
+        } else {
+            OpaError::Request(err)
+        }
+    }
+}
+
+impl From<url::ParseError> for OpaError {
+    fn from(err: url::ParseError) -> Self {
+        OpaError::Endpoint(err)
+    }
+}
+
+impl ErrorExtensions for OpaError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, extensions| {
+            extensions.set("code", self.code());
+            if let OpaError::Denied { reason, violations } = self {
+                if let Some(reason) = reason {
+                    extensions.set("reason", reason.as_str());
+                }
+                if !violations.is_empty() {
+                    extensions.set("violations", violations.clone());
+                }
+            }
+        })
+    }
+}
+
+/// How requests to OPA, or the database, which fail transiently should be retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts made for each request
+    pub attempts: u32,
+    /// The delay before the first retry, doubled on each subsequent retry
+    pub backoff: Duration,
+    /// The fraction of each delay which is randomised, within `0.0..=1.0`
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// The delay before the numbered retry, counting from one
+    ///
+    /// A jitter outside `0.0..=1.0` is clamped to it, and one which is not a number is ignored
+    pub fn delay(&self, retry: u32) -> Duration {
+        let jitter = match self.jitter {
+            jitter if jitter.is_nan() => 0.0,
+            jitter => jitter.clamp(0.0, 1.0) * rand::random::<f64>(),
+        };
+        self.backoff
+            .saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)))
+            .mul_f64(1.0 - jitter)
+    }
+}
+
+/// Whether a failed request to OPA may succeed if retried, i.e. a connection error or server error
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect()
+        || error.is_timeout()
+        || error
+            .status()
+            .is_some_and(|status| status.is_server_error())
+}
+
+/// How policy decisions are made whilst the [`CircuitBreaker`] is open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BreakerMode {
+    /// Deny all operations
+    Deny,
+    /// Allow read-only queries, deny all other operations
+    AllowReadOnly,
+}
+
+/// The state of a [`CircuitBreaker`]
 #[derive(Debug)]
+enum BreakerState {
+    /// Requests are permitted, counting consecutive failures
+    Closed {
+        /// The number of consecutive failed requests
+        failures: u32,
+    },
+    /// Requests are suspended until the cooldown elapses
+    Open {
+        /// When a trial request may next be made
+        until: Instant,
+    },
+    /// A single trial request is in flight
+    HalfOpen {
+        /// When the trial request was permitted
+        since: Instant,
+    },
+}
+
+/// A circuit breaker which suspends requests to OPA after repeated failures
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// The number of consecutive failures after which the breaker opens
+    threshold: u32,
+    /// How long the breaker remains open before permitting a trial request
+    cooldown: Duration,
+    /// How decisions are made whilst the breaker is open
+    mode: BreakerMode,
+    /// The current state of the breaker
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed [`CircuitBreaker`]
+    pub fn new(threshold: u32, cooldown: Duration, mode: BreakerMode) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            mode,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a request may be made, transitioning to half-open once the cooldown has elapsed
+    fn permit(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let trial = match *state {
+            BreakerState::Closed { .. } => return true,
+            BreakerState::Open { until } => now >= until,
+            BreakerState::HalfOpen { since } => now >= since + self.cooldown,
+        };
+        if trial {
+            *state = BreakerState::HalfOpen { since: now };
+        }
+        trial
+    }
+
+    /// Records a request which reached OPA, closing the breaker
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !matches!(*state, BreakerState::Closed { .. }) {
+            info!("OPA circuit breaker closed");
+            Metrics::get().opa_circuit_breaker_open.add(-1, &[]);
+        }
+        *state = BreakerState::Closed { failures: 0 };
+    }
+
+    /// Records a request which failed to reach OPA, opening the breaker if the threshold is met
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let open = match *state {
+            BreakerState::Closed { failures } if failures + 1 < self.threshold => {
+                *state = BreakerState::Closed {
+                    failures: failures + 1,
+                };
+                return;
+            }
+            BreakerState::Closed { .. } => false,
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => true,
+        };
+        if !open {
+            warn!("OPA circuit breaker opened");
+            let metrics = Metrics::get();
+            metrics.opa_circuit_breaker_open.add(1, &[]);
+            metrics.opa_circuit_breaker_trips.add(1, &[]);
+        }
+        *state = BreakerState::Open {
+            until: Instant::now() + self.cooldown,
+        };
+    }
+
+    /// Performs a request if the breaker permits it, otherwise falls back according to the [`BreakerMode`]
+    async fn call<T>(
+        &self,
+        read_only: bool,
+        fallback: impl FnOnce() -> T,
+        request: impl Future<Output = Result<T, OpaError>>,
+    ) -> Result<T, OpaError> {
+        if !self.permit() {
+            return match self.mode {
+                BreakerMode::AllowReadOnly if read_only => {
+                    warn!("OPA circuit breaker open, allowing read-only operation");
+                    Ok(fallback())
+                }
+                BreakerMode::AllowReadOnly | BreakerMode::Deny => Err(OpaError::CircuitOpen),
+            };
+        }
+        let result = request.await;
+        match &result {
+            Err(err) if err.is_outage() => self.record_failure(),
+            _ => self.record_success(),
+        }
+        result
+    }
+}
+
+/// Transport Layer Security configuration for connections to OPA
+#[derive(Debug)]
+pub struct OpaTls {
+    /// The paths of the PEM encoded client certificate chain and private key presented to OPA, if any
+    pub client_identity: Option<(PathBuf, PathBuf)>,
+    /// The path of a PEM encoded bundle of certificate authorities trusted to sign the OPA server certificate, if any
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl OpaTls {
+    /// Applies the configuration to a [`reqwest::ClientBuilder`]
+    fn configure(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, anyhow::Error> {
+        if let Some((certificate, key)) = &self.client_identity {
+            let mut pem = fs::read(certificate)?;
+            pem.push(b'\n');
+            pem.extend(fs::read(key)?);
+            builder = builder.identity(reqwest::Identity::from_pem(&pem)?);
+        }
+        if let Some(ca_bundle) = &self.ca_bundle {
+            for certificate in reqwest::Certificate::from_pem_bundle(&fs::read(ca_bundle)?)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(builder)
+    }
+}
+
+/// The tracing target to which authorization audit records are emitted
+pub const AUDIT_TARGET: &str = "audit";
+
+/// An authorization audit record, emitted once the decision has been made
+#[derive(Debug)]
+struct AuditRecord {
+    /// Information about the operation being authorized
+    context: DecisionContext,
+    /// The parameters of the decision, serialized as JSON
+    parameters: String,
+    /// When the decision was requested
+    start: Instant,
+}
+
+impl AuditRecord {
+    /// Starts an [`AuditRecord`] for a decision on the provided parameters
+    fn new(context: &DecisionContext, parameters: &impl Serialize) -> Self {
+        Self {
+            context: context.clone(),
+            parameters: serde_json::to_string(parameters).unwrap_or_default(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Emits the record to the [`AUDIT_TARGET`] with the outcome of the decision
+    fn emit(self, decision: &str) {
+        info!(
+            target: AUDIT_TARGET,
+            subject = self.context.subject,
+            service = self.context.service,
+            operation = self.context.operation,
+            parameters = self.parameters,
+            decision,
+            latency_seconds = self.start.elapsed().as_secs_f64(),
+            "Authorization decision"
+        );
+    }
+}
+
+/// The OPA Data API path of the manifests of the loaded bundles
+const BUNDLES_PATH: &str = "v1/data/system/bundles";
+
+/// A [`Cache`] of [`Decision`]s, cleared whenever the revisions of the bundles loaded by OPA change
+#[derive(Debug)]
+pub struct DecisionCache {
+    /// The cached decisions, keyed by policy and serialized input
+    cache: Cache,
+    /// The revisions of the loaded bundles when the cache was last cleared, if known
+    revisions: Mutex<Option<serde_json::Value>>,
+}
+
+impl DecisionCache {
+    /// Creates a [`DecisionCache`] holding decisions in the [`Cache`]
+    pub fn new(cache: Cache) -> Self {
+        Self {
+            cache,
+            revisions: Mutex::new(None),
+        }
+    }
+
+    /// Clears the cache if the bundle revisions differ from those last observed
+    async fn observe_revisions(&self, revisions: serde_json::Value) {
+        let changed = {
+            let mut observed = self.revisions.lock().unwrap();
+            let changed = observed
+                .as_ref()
+                .is_some_and(|observed| *observed != revisions);
+            *observed = Some(revisions.clone());
+            changed
+        };
+        if changed {
+            info!("OPA bundle revisions changed to {revisions}, clearing decision cache");
+            self.cache.clear().await;
+            Metrics::get().opa_decision_cache_invalidations.add(1, &[]);
+        }
+    }
+}
+
+/// An Open Policy Agent client, clones of which share a [`CircuitBreaker`] and [`DecisionCache`]
+#[derive(Debug, Clone)]
 pub struct OpaClient {
     /// A configured [`reqwest::Client`]
     client: reqwest::Client,
     /// The OPA endpoint to make requests against
     endpoint: Url,
+    /// How transiently failing requests are retried
+    retry: RetryPolicy,
+    /// Suspends requests whilst OPA is unavailable
+    breaker: Arc<CircuitBreaker>,
+    /// The path of a policy document, relative to the OPA Data API, which must be defined for OPA to be ready
+    required_policy: String,
+    /// Caches decisions across requests, if set
+    cache: Option<Arc<DecisionCache>>,
 }
 
 impl OpaClient {
-    /// Creates a new [`OpaClient`] bound to the provided endpoint [`Url`]
-    pub fn new(endpoint: Url) -> Self {
+    /// Creates a new [`OpaClient`] bound to the provided endpoint [`Url`], abandoning requests after the timeout
+    pub fn new(
+        endpoint: Url,
+        timeout: Duration,
+        retry: RetryPolicy,
+        breaker: CircuitBreaker,
+        tls: &OpaTls,
+        required_policy: String,
+        cache: Option<DecisionCache>,
+    ) -> Result<Self, anyhow::Error> {
         info!("Setting up OPA client at {endpoint}");
-        Self {
-            client: reqwest::Client::new(),
+        Ok(Self {
+            client: tls
+                .configure(reqwest::Client::builder().timeout(timeout))?
+                .build()?,
             endpoint,
+            retry,
+            breaker: Arc::new(breaker),
+            required_policy,
+            cache: cache.map(Arc::new),
+        })
+    }
+
+    /// Polls the revisions of the bundles loaded by OPA at the interval, clearing the [`DecisionCache`] when they change
+    pub async fn watch_revisions(self, interval: Duration) {
+        let Some(cache) = self.cache.clone() else {
+            return;
+        };
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match self.bundle_revisions().await {
+                Ok(revisions) => cache.observe_revisions(revisions).await,
+                Err(err) => warn!("Could not retrieve OPA bundle revisions: {err}"),
+            }
         }
     }
 
-    /// Queries OPA with the [`OpaInput`] and returns the [`Decision`]
-    #[instrument(skip(self, input))]
-    async fn query<P: Serialize>(&self, input: OpaInput<P>) -> Result<Decision, reqwest::Error> {
-        let mut request = self
+    /// Retrieves the revision of each bundle loaded by OPA, keyed by bundle name
+    async fn bundle_revisions(&self) -> Result<serde_json::Value, OpaError> {
+        let bundles = self
             .client
-            .post(self.endpoint.clone())
-            .json(&input)
-            .build()?;
-
-        opentelemetry::global::get_text_map_propagator(|propagator| {
-            propagator.inject_context(
-                &tracing::Span::current().context(),
-                &mut opentelemetry_http::HeaderInjector(request.headers_mut()),
+            .get(self.endpoint.join(BUNDLES_PATH)?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<DataResponse<serde_json::Map<String, serde_json::Value>>>()
+            .await?
+            .result
+            .unwrap_or_default();
+        Ok(bundles
+            .into_iter()
+            .map(|(name, bundle)| (name, bundle["manifest"]["revision"].clone()))
+            .collect())
+    }
+
+    /// Checks that OPA is healthy, all bundles are activated and the required policy is loaded
+    #[instrument(skip(self))]
+    pub async fn ready(&self) -> Result<(), OpaError> {
+        self.client
+            .get(self.endpoint.join("health?bundles")?)
+            .send()
+            .await?
+            .error_for_status()?;
+        self.client
+            .get(
+                self.endpoint
+                    .join(&format!("v1/data/{}", self.required_policy))?,
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<DataResponse<serde_json::Value>>()
+            .await?
+            .result
+            .map(|_| ())
+            .ok_or_else(|| OpaError::PolicyNotLoaded(self.required_policy.clone()))
+    }
+
+    /// Executes a request against OPA, retrying according to the [`RetryPolicy`] if it fails transiently, and records the
+    /// duration of each attempt labelled by the policy
+    async fn execute(
+        &self,
+        policy: &str,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut retry = 0;
+        loop {
+            let attempt = request
+                .try_clone()
+                .expect("OPA requests should have buffered bodies");
+            let start = Instant::now();
+            let result = self
+                .client
+                .execute(attempt)
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            Metrics::get().opa_request_duration_seconds.record(
+                start.elapsed().as_secs_f64(),
+                &[
+                    opentelemetry::KeyValue::new("policy", policy.to_string()),
+                    opentelemetry::KeyValue::new(
+                        "status",
+                        match &result {
+                            Ok(_) => "success",
+                            Err(_) => "error",
+                        },
+                    ),
+                ],
+            );
+            match result {
+                Err(err) if is_transient(&err) && retry + 1 < self.retry.attempts => {
+                    retry += 1;
+                    let delay = self.retry.delay(retry);
+                    warn!("OPA request failed, retrying in {delay:?}: {err}");
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the [`Decision`] of the policy at the path, or of the default decision if [`None`], on the [`OpaInput`],
+    /// reusing any identical decision already made whilst handling the request
+    async fn query<P: Serialize>(
+        &self,
+        policy: Option<&str>,
+        input: OpaInput<P>,
+    ) -> Result<Decision, OpaError> {
+        match (input.memo.clone(), decision_key(policy, &input)) {
+            (Some(memo), Some(key)) => memo
+                .cell(&key)
+                .get_or_try_init(|| self.query_shared(policy, input))
+                .await
+                .cloned(),
+            _ => self.query_shared(policy, input).await,
+        }
+    }
+
+    /// Queries OPA with the [`OpaInput`] and returns the [`Decision`] of the policy at the path, or of the default decision if [`None`],
+    /// reusing any unexpired decision in the [`DecisionCache`]
+    #[instrument(skip(self, input))]
+    async fn query_shared<P: Serialize>(
+        &self,
+        policy: Option<&str>,
+        input: OpaInput<P>,
+    ) -> Result<Decision, OpaError> {
+        let read_only = input.context.read_only;
+        let baggage = input.context.baggage();
+        let cache_key = self
+            .cache
+            .as_ref()
+            .and_then(|_| decision_key(policy, &input));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(decision) = cache.cache.get(key).await {
+                return Ok(decision);
+            }
+        }
+        self.breaker
+            .call(
+                read_only,
+                || Decision {
+                    allow: true,
+                    reason: None,
+                    violations: Vec::new(),
+                },
+                async {
+                    let mut request = match policy {
+                        Some(policy) => self
+                            .client
+                            .post(self.endpoint.join(&format!("v1/data/{policy}"))?)
+                            .json(&DataRequest { input: &input }),
+                        None => self.client.post(self.endpoint.clone()).json(&input),
+                    }
+                    .build()?;
+                    inject_trace_context(&mut request, &baggage);
+
+                    let response = self
+                        .execute(policy.unwrap_or(DEFAULT_POLICY), request)
+                        .await?;
+                    let decision = match policy {
+                        Some(_) => response
+                            .json::<DataResponse<Decision>>()
+                            .await?
+                            .result
+                            .unwrap_or(Decision {
+                                allow: false,
+                                reason: None,
+                                violations: Vec::new(),
+                            }),
+                        None => response.json().await?,
+                    };
+                    if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                        cache.cache.insert(key, &decision).await;
+                    }
+                    Ok(decision)
+                },
             )
+            .await
+    }
+
+    /// Partially evaluates the policy with the [`OpaPartialInput`] and returns the residual [`PartialDecision`]
+    #[instrument(skip(self, input))]
+    async fn query_compile(&self, input: OpaPartialInput) -> Result<PartialDecision, OpaError> {
+        let read_only = input.context.read_only;
+        let baggage = input.context.baggage();
+        self.breaker
+            .call(
+                read_only,
+                || PartialDecision {
+                    queries: vec![Vec::new()],
+                },
+                async {
+                    let mut request = self
+                        .client
+                        .post(self.endpoint.join("v1/compile")?)
+                        .json(&CompileRequest {
+                            query: COMPILE_QUERY,
+                            input,
+                            unknowns: COMPILE_UNKNOWNS,
+                        })
+                        .build()?;
+                    inject_trace_context(&mut request, &baggage);
+
+                    Ok(self
+                        .execute(DEFAULT_POLICY, request)
+                        .await?
+                        .json::<CompileResponse>()
+                        .await?
+                        .result)
+                },
+            )
+            .await
+    }
+
+    /// Partially evaluates the policy with the [`OpaPartialInput`] and returns the residual [`PartialDecision`]
+    pub async fn compile(&self, input: OpaPartialInput) -> Result<PartialDecision, OpaError> {
+        let audit = AuditRecord::new(&input.context, &None::<()>);
+        let result = self.query_compile(input).await;
+        let outcome = match &result {
+            Ok(partial) if partial.queries.is_empty() => "deny",
+            Ok(_) => "partial",
+            Err(_) => "error",
+        };
+        record_decisions(DEFAULT_POLICY, outcome, 1);
+        audit.emit(outcome);
+        result
+    }
+
+    /// Queries OPA with the [`OpaInput`] and returns a [`Result`] of the policy at the path, or of the default decision if [`None`]
+    pub async fn decide<P: Serialize>(
+        &self,
+        policy: Option<&str>,
+        input: OpaInput<P>,
+    ) -> Result<(), OpaError> {
+        let audit = AuditRecord::new(&input.context, &input.parameters);
+        let label = policy.unwrap_or(DEFAULT_POLICY).to_string();
+        let result = self.query(policy, input).await.and_then(|decision| {
+            if decision.allow {
+                Ok(())
+            } else {
+                Err(OpaError::Denied {
+                    reason: decision.reason.as_deref().map(sanitise_reason),
+                    violations: decision
+                        .violations
+                        .iter()
+                        .take(MAX_VIOLATIONS)
+                        .map(|violation| sanitise_reason(violation))
+                        .collect(),
+                })
+            }
         });
+        let outcome = match &result {
+            Ok(()) => "allow",
+            Err(OpaError::Denied { .. }) => "deny",
+            Err(_) => "error",
+        };
+        record_decisions(&label, outcome, 1);
+        audit.emit(outcome);
+        result
+    }
 
-        self.client.execute(request).await?.json().await
+    /// Queries OPA with a batch of parameters and returns the [`BatchDecision`]
+    #[instrument(skip(self, input))]
+    async fn query_batch<P: Serialize>(
+        &self,
+        input: OpaInput<Vec<P>>,
+    ) -> Result<BatchDecision, OpaError> {
+        let read_only = input.context.read_only;
+        let baggage = input.context.baggage();
+        let count = input.parameters.len();
+        self.breaker
+            .call(
+                read_only,
+                || BatchDecision {
+                    allowed: vec![true; count],
+                },
+                async {
+                    let mut request = self
+                        .client
+                        .post(self.endpoint.join(BATCH_PATH)?)
+                        .json(&DataRequest { input })
+                        .build()?;
+
+                    inject_trace_context(&mut request, &baggage);
+
+                    Ok(self
+                        .execute(BATCH_POLICY, request)
+                        .await?
+                        .json::<DataResponse<BatchDecision>>()
+                        .await?
+                        .result
+                        .unwrap_or(BatchDecision {
+                            allowed: Vec::new(),
+                        }))
+                },
+            )
+            .await
     }
 
-    /// Queries OPA with the [`OpaInput`] and returns a [`Result`]
-    pub async fn decide<P: Serialize>(&self, input: OpaInput<P>) -> Result<(), anyhow::Error> {
-        self.query(input)
-            .await?
-            .allow
-            .then_some(())
-            .ok_or(anyhow::anyhow!("Access denied"))
+    /// Queries OPA once with the parameters of every candidate and returns those which are permitted
+    ///
+    /// The parameters in the [`OpaInput`] must correspond, in order, to the candidates
+    pub async fn decide_batch<P: Serialize, T>(
+        &self,
+        input: OpaInput<Vec<P>>,
+        candidates: Vec<T>,
+    ) -> Result<Vec<T>, OpaError> {
+        if candidates.is_empty() {
+            return Ok(candidates);
+        }
+        let audit = AuditRecord::new(&input.context, &input.parameters);
+        let allowed = match self.query_batch(input).await {
+            Ok(decision) if decision.allowed.len() == candidates.len() => decision.allowed,
+            Ok(decision) => {
+                record_decisions(BATCH_POLICY, "error", 1);
+                audit.emit("error");
+                return Err(OpaError::InvalidDecision(format!(
+                    "expected {} decisions, received {}",
+                    candidates.len(),
+                    decision.allowed.len()
+                )));
+            }
+            Err(err) => {
+                record_decisions(BATCH_POLICY, "error", 1);
+                audit.emit("error");
+                return Err(err);
+            }
+        };
+        let permitted = allowed.iter().filter(|allow| **allow).count();
+        record_decisions(BATCH_POLICY, "allow", permitted as u64);
+        record_decisions(BATCH_POLICY, "deny", (allowed.len() - permitted) as u64);
+        audit.emit(&format!("allow {permitted} of {}", allowed.len()));
+        Ok(candidates
+            .into_iter()
+            .zip(allowed)
+            .filter_map(|(candidate, allow)| allow.then_some(candidate))
+            .collect())
+    }
+}
+
+/// The path of the policy, relative to the OPA Data API, which authorizes access to non-sensitive metadata
+///
+/// Pointing this at a policy which does not require a token permits anonymous access to such fields
+#[derive(Debug, Clone)]
+pub struct PublicPolicy(pub String);
+
+/// The policy consulted by an [`OpaGuard`]
+#[derive(Debug)]
+enum GuardPolicy {
+    /// The policy at a fixed path, relative to the OPA Data API
+    Path(&'static str),
+    /// The configured [`PublicPolicy`]
+    Public,
+}
+
+/// An [`async_graphql::Guard`] which permits resolution of a field only if allowed by the OPA policy at a path
+///
+/// ```ignore
+/// #[graphql(guard = OpaGuard::new("sessions/read", parameters))]
+/// ```
+#[derive(Debug)]
+pub struct OpaGuard<P: Serialize> {
+    /// The policy consulted
+    policy: GuardPolicy,
+    /// Additional parameters required by OPA
+    parameters: P,
+}
+
+impl<P: Serialize> OpaGuard<P> {
+    /// Creates an [`OpaGuard`] deciding on the parameters with the policy at the path, e.g. `sessions/read`
+    pub fn new(policy: &'static str, parameters: P) -> Self {
+        Self {
+            policy: GuardPolicy::Path(policy),
+            parameters,
+        }
+    }
+
+    /// Creates an [`OpaGuard`] deciding on the parameters with the configured [`PublicPolicy`], for non-sensitive fields
+    pub fn public(parameters: P) -> Self {
+        Self {
+            policy: GuardPolicy::Public,
+            parameters,
+        }
+    }
+}
+
+impl<P: Serialize + Sync> Guard for OpaGuard<P> {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> Result<(), async_graphql::Error> {
+        let policy = match &self.policy {
+            GuardPolicy::Path(policy) => policy,
+            GuardPolicy::Public => ctx.data::<PublicPolicy>()?.0.as_str(),
+        };
+        ctx.data::<OpaClient>()?
+            .decide(Some(policy), OpaInput::new(ctx, &self.parameters))
+            .await
+            .extend()
+    }
+}
+
+/// Propagates the current tracing span to OPA via the request headers, with the baggage added to that of the span and
+/// recorded as attributes of it
+fn inject_trace_context(request: &mut reqwest::Request, baggage: &[opentelemetry::KeyValue]) {
+    let span = tracing::Span::current();
+    for entry in baggage {
+        span.set_attribute(entry.key.clone(), entry.value.clone());
+    }
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(
+            &span.context().with_baggage(baggage.to_vec()),
+            &mut opentelemetry_http::HeaderInjector(request.headers_mut()),
+        )
+    });
+}
+
+/// Tests of the translation of residual policies into SQL conditions, and of the resilience of requests to OPA
+#[cfg(test)]
+mod tests {
+    use super::{
+        BreakerMode, CircuitBreaker, OpaError, ParameterColumn, PartialDecision, RetryPolicy,
+    };
+    use proptest::prelude::*;
+    use sea_orm::{
+        sea_query::{Alias, Asterisk, Expr, MysqlQueryBuilder, Query},
+        Condition,
+    };
+    use serde_json::{json, Value};
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+
+    /// How long the breakers of the tests remain open
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    /// The column of each parameter, the proposal being textual and the visit native
+    fn column(parameter: &str) -> Option<ParameterColumn> {
+        match parameter {
+            "proposal" => Some(ParameterColumn::Text(Expr::col(Alias::new("proposal")))),
+            "visit" => Some(ParameterColumn::Native(Expr::col(Alias::new("visit")))),
+            _ => None,
+        }
+    }
+
+    /// A reference to the input parameter
+    fn parameter(name: &str) -> Value {
+        json!({ "type": "ref", "value": [
+            { "type": "var", "value": "input" },
+            { "type": "string", "value": "parameters" },
+            { "type": "string", "value": name },
+        ] })
+    }
+
+    /// An expression applying the operator to the input parameter and the literal value
+    fn call(operator: &str, name: &str, value: Value) -> Value {
+        json!({ "terms": [
+            { "type": "ref", "value": [{ "type": "var", "value": operator }] },
+            parameter(name),
+            value,
+        ] })
+    }
+
+    /// Translates the residual policy with the columns of [`column`]
+    fn translate(decision: Value) -> Result<Option<Condition>, anyhow::Error> {
+        serde_json::from_value::<PartialDecision>(decision)
+            .expect("Residual policy should be valid")
+            .into_condition(column)
+    }
+
+    /// The SQL selecting the sessions satisfying the condition
+    fn sql(condition: Condition) -> String {
+        Query::select()
+            .column(Asterisk)
+            .from(Alias::new("session"))
+            .cond_where(condition)
+            .to_string(MysqlQueryBuilder)
+    }
+
+    #[test]
+    fn empty_query_is_unconditional() {
+        let condition = translate(json!({ "queries": [[]] }))
+            .unwrap()
+            .expect("Access should be permitted");
+        assert_eq!(sql(condition), "SELECT * FROM `session` WHERE TRUE");
+    }
+
+    #[test]
+    fn no_queries_deny() {
+        assert!(translate(json!({ "queries": [] })).unwrap().is_none());
+        assert!(translate(json!({})).unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_parameter_is_rejected() {
+        let err = translate(json!({ "queries": [[
+            call("eq", "beamline", json!({ "type": "string", "value": "i03" })),
+        ]] }))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported residual policy parameter: beamline"
+        );
+    }
+
+    #[test]
+    fn reference_outside_parameters_is_rejected() {
+        let err = translate(json!({ "queries": [[
+            { "terms": { "type": "ref", "value": [
+                { "type": "var", "value": "data" },
+                { "type": "string", "value": "allowed" },
+            ] } },
+        ]] }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported residual policy expression");
+    }
+
+    #[test]
+    fn queries_are_disjunctions_of_conjunctions() {
+        let condition = translate(json!({ "queries": [
+            [
+                call("eq", "proposal", json!({ "type": "number", "value": 31111 })),
+                call("gte", "visit", json!({ "type": "number", "value": 2 })),
+            ],
+            [
+                { "negated": true, "terms": [
+                    { "type": "ref", "value": [{ "type": "var", "value": "lt" }] },
+                    { "type": "number", "value": 5 },
+                    parameter("visit"),
+                ] },
+            ],
+        ] }))
+        .unwrap()
+        .expect("Access should be permitted");
+        assert_eq!(
+            sql(condition),
+            "SELECT * FROM `session` WHERE (`proposal` = '31111' AND `visit` >= 2) OR (NOT `visit` > 5)"
+        );
+    }
+
+    #[test]
+    fn textual_parameter_is_not_ordered() {
+        let err = translate(json!({ "queries": [[
+            call("lt", "proposal", json!({ "type": "number", "value": 31111 })),
+        ]] }))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unsupported residual policy comparison of textual parameter: proposal"
+        );
+    }
+
+    /// An error of a request which failed to reach OPA
+    async fn outage() -> OpaError {
+        OpaError::Request(
+            reqwest::get("http://127.0.0.1:1")
+                .await
+                .expect_err("Nothing should listen on port 1"),
+        )
+    }
+
+    /// Makes a request which fails to reach OPA through the breaker
+    async fn fail(breaker: &CircuitBreaker) -> Result<(), OpaError> {
+        let err = outage().await;
+        breaker.call(false, || (), async { Err(err) }).await
+    }
+
+    /// Makes a request which reaches OPA through the breaker, returning whether it was made
+    async fn succeed(breaker: &CircuitBreaker) -> Result<bool, OpaError> {
+        let made = AtomicBool::new(false);
+        breaker
+            .call(false, || (), async {
+                made.store(true, Ordering::Relaxed);
+                Ok(())
+            })
+            .await?;
+        Ok(made.load(Ordering::Relaxed))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_opens_at_threshold() {
+        let breaker = CircuitBreaker::new(2, COOLDOWN, BreakerMode::Deny);
+        assert!(matches!(fail(&breaker).await, Err(OpaError::Request(_))));
+        assert!(matches!(fail(&breaker).await, Err(OpaError::Request(_))));
+        assert!(matches!(
+            succeed(&breaker).await,
+            Err(OpaError::CircuitOpen)
+        ));
+        tokio::time::advance(COOLDOWN - Duration::from_millis(1)).await;
+        assert!(matches!(
+            succeed(&breaker).await,
+            Err(OpaError::CircuitOpen)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn success_resets_failures() {
+        let breaker = CircuitBreaker::new(2, COOLDOWN, BreakerMode::Deny);
+        fail(&breaker).await.unwrap_err();
+        assert!(succeed(&breaker).await.unwrap());
+        fail(&breaker).await.unwrap_err();
+        assert!(succeed(&breaker).await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_closes_after_successful_trial() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN, BreakerMode::Deny);
+        fail(&breaker).await.unwrap_err();
+        tokio::time::advance(COOLDOWN).await;
+        assert!(succeed(&breaker).await.unwrap());
+        assert!(succeed(&breaker).await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_reopens_after_failed_trial() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN, BreakerMode::Deny);
+        fail(&breaker).await.unwrap_err();
+        tokio::time::advance(COOLDOWN).await;
+        assert!(matches!(fail(&breaker).await, Err(OpaError::Request(_))));
+        assert!(matches!(
+            succeed(&breaker).await,
+            Err(OpaError::CircuitOpen)
+        ));
+        tokio::time::advance(COOLDOWN).await;
+        assert!(succeed(&breaker).await.unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_open_breaker_permits_one_trial_per_cooldown() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN, BreakerMode::Deny);
+        fail(&breaker).await.unwrap_err();
+        tokio::time::advance(COOLDOWN).await;
+        assert!(breaker.permit());
+        assert!(!breaker.permit());
+        tokio::time::advance(COOLDOWN).await;
+        assert!(breaker.permit());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn open_breaker_allows_only_read_only_operations() {
+        let breaker = CircuitBreaker::new(1, COOLDOWN, BreakerMode::AllowReadOnly);
+        fail(&breaker).await.unwrap_err();
+        let read = breaker
+            .call(true, || "fallback", async { Ok("decision") })
+            .await;
+        assert_eq!(read.unwrap(), "fallback");
+        let write = breaker
+            .call(false, || "fallback", async { Ok("decision") })
+            .await;
+        assert!(matches!(write, Err(OpaError::CircuitOpen)));
+    }
+
+    #[test]
+    fn delay_without_jitter_doubles() {
+        let retry = RetryPolicy {
+            attempts: 4,
+            backoff: Duration::from_millis(100),
+            jitter: 0.0,
+        };
+        assert_eq!(retry.delay(1), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(200));
+        assert_eq!(retry.delay(3), Duration::from_millis(400));
+    }
+
+    proptest! {
+        #[test]
+        fn delay_is_within_jitter_of_backoff(
+            retry in 1_u32..16,
+            jitter in prop_oneof![-1.0..2.0, Just(f64::NAN)],
+        ) {
+            let policy = RetryPolicy {
+                attempts: 16,
+                backoff: Duration::from_millis(100),
+                jitter,
+            };
+            let backoff = Duration::from_millis(100) * 2_u32.pow(retry - 1);
+            let delay = policy.delay(retry);
+            prop_assert!(delay <= backoff);
+            let fraction = if jitter.is_nan() { 0.0 } else { jitter.clamp(0.0, 1.0) };
+            prop_assert!(delay >= backoff.mul_f64(1.0 - fraction));
+        }
     }
 }
@@ -0,0 +1,83 @@
+use crate::route_handlers::{
+    __path_export_csv, __path_export_tsv, __path_get_log_level, __path_get_session,
+    __path_liveness, __path_metrics, __path_readiness, __path_set_log_level, RestError, RestErrors,
+    RestProposal, RestSession,
+};
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as Document,
+    },
+    OpenApi,
+};
+
+/// The REST endpoints serving sessions, at paths relative to the endpoint path
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Sessions",
+        description = "The routes of the service other than GraphQL, which is served at the endpoint path",
+        license(name = "Apache-2.0", url = "https://www.apache.org/licenses/LICENSE-2.0")
+    ),
+    paths(get_session, export_csv, export_tsv),
+    components(schemas(RestSession, RestProposal, RestErrors, RestError)),
+    tags((name = "sessions", description = "Beamline Sessions, authorized exactly as the GraphQL queries"))
+)]
+struct RestApi;
+
+/// The probes, metrics and log level endpoint, served alongside the API unless an admin listener is configured
+#[derive(OpenApi)]
+#[openapi(
+    paths(liveness, readiness, metrics, get_log_level, set_log_level),
+    tags(
+        (name = "probes", description = "Liveness, readiness and metrics of the service"),
+        (name = "admin", description = "Runtime administration of the service")
+    )
+)]
+struct AdminApi;
+
+/// The OpenAPI document of the REST endpoints, beneath the endpoint path, and of the probes, metrics and log level endpoint
+/// if they are served alongside them
+///
+/// Callers of the REST endpoints may authenticate with a bearer token or with an API key in the header
+pub fn document(endpoint_path: &str, api_key_header: &str, admin: bool) -> Document {
+    let mut document = RestApi::openapi();
+    let components = document.components.get_or_insert_with(Default::default);
+    components.add_security_scheme(
+        "bearer",
+        SecurityScheme::Http(
+            HttpBuilder::new()
+                .scheme(HttpAuthScheme::Bearer)
+                .bearer_format("JWT")
+                .build(),
+        ),
+    );
+    components.add_security_scheme(
+        "api_key",
+        SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(api_key_header))),
+    );
+    if endpoint_path != "/" {
+        document.paths.paths = std::mem::take(&mut document.paths.paths)
+            .into_iter()
+            .map(|(path, item)| (format!("{endpoint_path}{path}"), item))
+            .collect();
+    }
+    if admin {
+        document.merge(AdminApi::openapi());
+    }
+    document
+}
+
+/// Snapshots of the OpenAPI document, such that changes to it are deliberate
+#[cfg(test)]
+mod tests {
+    use super::document;
+
+    #[test]
+    fn document_matches_snapshot() {
+        let json = document("/", "x-api-key", true)
+            .to_pretty_json()
+            .expect("Document should be serializable");
+        insta::assert_snapshot!(json);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::metrics::Metrics;
+use async_graphql::{
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+    },
+    parser::types::{DocumentOperations, ExecutableDocument, OperationType},
+    Response, ServerResult, Variables,
+};
+use opentelemetry::KeyValue;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// An [`ExtensionFactory`] recording the duration, count and errors of each GraphQL operation, labelled by its name and type
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OperationMetrics;
+
+impl ExtensionFactory for OperationMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationMetricsExtension::default())
+    }
+}
+
+/// The operations of a single request, as they are parsed and executed
+#[derive(Debug, Default)]
+struct OperationMetricsExtension {
+    /// The types of the operations in the parsed document, keyed by their names
+    operations: Mutex<HashMap<Option<String>, OperationType>>,
+    /// The name of the executed operation, once selected
+    executed: Mutex<Option<Option<String>>>,
+}
+
+impl OperationMetricsExtension {
+    /// The name and type of the executed operation or, if the request failed before one was selected, the only operation of
+    /// the document, empty and `unknown` if neither is known
+    fn operation(&self) -> (String, &'static str) {
+        let operations = self.operations.lock().unwrap();
+        let name = match self.executed.lock().unwrap().clone() {
+            Some(name) => name,
+            None if operations.len() == 1 => operations.keys().next().cloned().flatten(),
+            None => return (String::new(), "unknown"),
+        };
+        let ty = match operations.get(&name) {
+            Some(OperationType::Query) => "query",
+            Some(OperationType::Mutation) => "mutation",
+            Some(OperationType::Subscription) => "subscription",
+            None => "unknown",
+        };
+        (name.unwrap_or_default(), ty)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationMetricsExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let start = Instant::now();
+        let response = next.run(ctx).await;
+        let (operation, operation_type) = self.operation();
+        let metrics = Metrics::get();
+        let attributes = [
+            KeyValue::new("operation", operation),
+            KeyValue::new("operation_type", operation_type),
+        ];
+        metrics
+            .graphql_operation_duration_seconds
+            .record(start.elapsed().as_secs_f64(), &attributes);
+        metrics.graphql_operations.add(1, &attributes);
+        if !response.errors.is_empty() {
+            metrics
+                .graphql_errors
+                .add(response.errors.len() as u64, &attributes);
+        }
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        *self.operations.lock().unwrap() = match &document.operations {
+            DocumentOperations::Single(operation) => HashMap::from([(None, operation.node.ty)]),
+            DocumentOperations::Multiple(operations) => operations
+                .iter()
+                .map(|(name, operation)| (Some(name.to_string()), operation.node.ty))
+                .collect(),
+        };
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        *self.executed.lock().unwrap() = Some(operation_name.map(str::to_string));
+        next.run(ctx, operation_name).await
+    }
+}
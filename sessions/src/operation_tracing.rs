@@ -0,0 +1,113 @@
+use async_graphql::{
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+    },
+    parser::types::{DocumentOperations, ExecutableDocument, OperationType},
+    Response, ServerResult, Variables,
+};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use tracing::{field::Empty, info_span, Instrument, Span};
+
+/// An [`ExtensionFactory`] wrapping each GraphQL operation in a span named by its type and name, carrying the
+/// `graphql.operation.name`, `graphql.operation.type` and `graphql.document.hash` attributes such that traces can be found by
+/// operation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OperationTracing;
+
+impl ExtensionFactory for OperationTracing {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(OperationTracingExtension {
+            span: info_span!(
+                "graphql_operation",
+                otel.name = "GraphQL Operation",
+                graphql.operation.name = Empty,
+                "graphql.operation.type" = Empty,
+                graphql.document.hash = Empty,
+            ),
+            operations: Mutex::default(),
+        })
+    }
+}
+
+/// The span of a single request, and the operations of its document once parsed
+#[derive(Debug)]
+struct OperationTracingExtension {
+    /// The span within which the operation is executed
+    span: Span,
+    /// The names and types of the operations in the parsed document
+    operations: Mutex<Vec<(Option<String>, OperationType)>>,
+}
+
+/// The name, as used in the OpenTelemetry semantic conventions, of the operation type
+fn operation_type_name(ty: OperationType) -> &'static str {
+    match ty {
+        OperationType::Query => "query",
+        OperationType::Mutation => "mutation",
+        OperationType::Subscription => "subscription",
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for OperationTracingExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        next.run(ctx).instrument(self.span.clone()).await
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        if !query.is_empty() {
+            self.span.record(
+                "graphql.document.hash",
+                format!("{:x}", Sha256::digest(query.as_bytes())),
+            );
+        }
+        let document = next.run(ctx, query, variables).await?;
+        *self.operations.lock().unwrap() = match &document.operations {
+            DocumentOperations::Single(operation) => vec![(None, operation.node.ty)],
+            DocumentOperations::Multiple(operations) => operations
+                .iter()
+                .map(|(name, operation)| (Some(name.to_string()), operation.node.ty))
+                .collect(),
+        };
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let operation = {
+            let operations = self.operations.lock().unwrap();
+            match operation_name {
+                Some(name) => operations
+                    .iter()
+                    .find(|(candidate, _)| candidate.as_deref() == Some(name))
+                    .cloned(),
+                None if operations.len() == 1 => operations.first().cloned(),
+                None => None,
+            }
+        };
+        if let Some((name, ty)) = operation {
+            let ty = operation_type_name(ty);
+            self.span.record("graphql.operation.type", ty);
+            match name {
+                Some(name) => {
+                    self.span.record("graphql.operation.name", name.as_str());
+                    self.span.record("otel.name", format!("{ty} {name}"));
+                }
+                None => {
+                    self.span.record("otel.name", ty);
+                }
+            }
+        }
+        next.run(ctx, operation_name).await
+    }
+}
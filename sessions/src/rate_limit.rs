@@ -0,0 +1,129 @@
+use crate::{jwt::JwtValidator, metrics::Metrics};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::headers::{authorization::Bearer, Authorization, HeaderMapExt};
+use governor::{
+    clock::{Clock, DefaultClock},
+    DefaultKeyedRateLimiter, Quota,
+};
+use serde_json::json;
+use std::{net::SocketAddr, num::NonZeroU32, sync::Arc, time::Duration};
+
+/// The interval at which the state of clients which have not made requests recently is discarded
+const RETAIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits the rate of requests made by each client, identified by the subject of its bearer token or otherwise by its address
+///
+/// Clients are only identified by their subject once the token has been validated by the [`JwtValidator`], such that a
+/// client presenting forged tokens shares the limit of its address. Without a [`JwtValidator`] every client is identified by
+/// its address
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    /// The state of each client, keyed by its identity
+    limiter: Arc<DefaultKeyedRateLimiter<String>>,
+    /// The validator of bearer tokens, from which the subject is read
+    validator: Option<Arc<JwtValidator>>,
+    /// Whether the client address is read from the `X-Forwarded-For` header set by a trusted proxy
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    /// Creates a [`RateLimiter`] permitting each client the requests per second, with bursts of up to the burst size,
+    /// periodically discarding the state of clients which have not made requests recently
+    pub fn new(per_second: NonZeroU32, burst: NonZeroU32, trust_forwarded_for: bool) -> Self {
+        let limiter = Arc::new(DefaultKeyedRateLimiter::keyed(
+            Quota::per_second(per_second).allow_burst(burst),
+        ));
+        tokio::spawn({
+            let limiter = Arc::downgrade(&limiter);
+            async move {
+                let mut ticker = tokio::time::interval(RETAIN_INTERVAL);
+                loop {
+                    ticker.tick().await;
+                    let Some(limiter) = limiter.upgrade() else {
+                        return;
+                    };
+                    limiter.retain_recent();
+                }
+            }
+        });
+        Self {
+            limiter,
+            validator: None,
+            trust_forwarded_for,
+        }
+    }
+
+    /// Identifies clients by the subject of bearer tokens validated by the [`JwtValidator`]
+    pub fn with_jwt_validator(mut self, validator: Option<Arc<JwtValidator>>) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// The identity of the client making a request, as the subject of its validated bearer token or otherwise its address
+    ///
+    /// Clients connected over a Unix domain socket have no peer address, such that they share a single limit unless
+    /// forwarded addresses are trusted
+    async fn client(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+        let subject = match (
+            &self.validator,
+            headers.typed_get::<Authorization<Bearer>>(),
+        ) {
+            (Some(validator), Some(header)) => validator
+                .validate(header.token())
+                .await
+                .ok()
+                .and_then(|claims| claims.subject().map(str::to_string)),
+            _ => None,
+        };
+        if let Some(subject) = subject {
+            return format!("subject:{subject}");
+        }
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for")?.to_str().ok())
+            .flatten()
+            .and_then(|forwarded| forwarded.split(',').next())
+            .map(str::trim);
+        match forwarded {
+            Some(address) => format!("address:{address}"),
+            None => match peer {
+                Some(peer) => format!("address:{}", peer.ip()),
+                None => "address:unix".to_string(),
+            },
+        }
+    }
+}
+
+/// Rejects requests from clients which have exceeded their rate with [`StatusCode::TOO_MANY_REQUESTS`], a `Retry-After`
+/// header and a GraphQL error
+pub async fn limit_rate(
+    State(limiter): State<RateLimiter>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = limiter
+        .client(request.headers(), peer.map(|ConnectInfo(peer)| peer))
+        .await;
+    match limiter.limiter.check_key(&client) {
+        Ok(()) => next.run(request).await,
+        Err(not_until) => {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            Metrics::get().rate_limited_requests.add(1, &[]);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, wait.as_secs().max(1).to_string())],
+                Json(json!({
+                    "errors": [{ "message": "Rate limit exceeded, retry later" }]
+                })),
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,55 +1,1164 @@
-use async_graphql::Executor;
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use crate::{
+    api_key::{ApiKeys, ServiceIdentity},
+    database::Databases,
+    jwt::{Claims, JwtError, JwtValidator},
+    log_level::LogLevel,
+    metrics::Metrics,
+    opa::{DecisionMemo, OpaClient},
+};
+use async_graphql::{
+    http::ALL_WEBSOCKET_PROTOCOLS,
+    parser::types::{DocumentOperations, OperationType},
+    BatchRequest, BatchResponse, CacheControl, Data, Executor,
+};
+use async_graphql_axum::{
+    GraphQLBatchRequest, GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket,
+};
 use axum::{
-    extract::Request,
+    body::Body,
+    extract::{ws::WebSocketUpgrade, MatchedPath, Path, Query, Request, State},
     handler::Handler,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    RequestExt,
+    http::{
+        header::{
+            ALLOW, AUTHORIZATION, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, COOKIE,
+            RETRY_AFTER, VARY, WWW_AUTHENTICATE,
+        },
+        HeaderMap, HeaderName, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    BoxError, Json, RequestExt,
 };
-use axum_extra::{
-    headers::{authorization::Bearer, Authorization},
-    TypedHeader,
+use axum_extra::headers::{
+    authorization::Bearer, Authorization, Cookie, ETag, HeaderMapExt, IfNoneMatch,
 };
-use std::{future::Future, pin::Pin};
+use futures::{future::join_all, stream, StreamExt};
+use opentelemetry::KeyValue;
+use prometheus::{Registry, TextEncoder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tower::load_shed::error::Overloaded;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// The query made by the REST and gRPC endpoints retrieving a single session, selecting every field of the session and its
+/// proposal
+pub const SESSION_QUERY: &str = r#"query LookupSession($proposalCode: String!, $proposalNumber: Int!, $visit: Int!) {
+    session(proposalCode: $proposalCode, proposalNumber: $proposalNumber, visit: $visit) {
+        id visit start end beamline proposal { code number state }
+    }
+}"#;
+
+/// The query made by the REST and gRPC endpoints listing sessions, selecting every field of the sessions and their proposals
+pub const SESSIONS_QUERY: &str = r#"query ListSessions($proposalCode: String, $proposalNumber: Int, $limit: Int, $offset: Int) {
+    sessions(proposalCode: $proposalCode, proposalNumber: $proposalNumber, limit: $limit, offset: $offset) {
+        id visit start end beamline proposal { code number state }
+    }
+}"#;
 
 /// An [`Handler`] which executes an [`Executor`] including the [`Authorization<Bearer>`] in the [`async_graphql::Context`]
+///
+/// If a token cookie is configured, its value is used as the bearer token when no [`Authorization<Bearer>`] header is present.
+/// If a [`JwtValidator`] is configured, requests bearing invalid tokens are rejected and the [`Claims`] of valid tokens are included in the [`async_graphql::Context`].
+/// If [`ApiKeys`] are configured, requests bearing an API key are authenticated as the corresponding [`ServiceIdentity`], which is included in the [`async_graphql::Context`].
+/// If a service token header is configured, requests bearing a client credentials token in it are authenticated as the [`ServiceIdentity`] of its `client_id`,
+/// acting on behalf of the user identified by the bearer token, if any
+/// Queries may be made over GET, as per the GraphQL-over-HTTP specification, such that their responses may be cached according
+/// to the cache control hints of the schema, or the configured maximum age of the operation, and revalidated by their [`ETag`];
+/// other operations made over GET are rejected with [`StatusCode::METHOD_NOT_ALLOWED`]
+/// Batches of operations may be made in a single POST, up to a maximum size, in which case the operations are executed concurrently
+/// A fresh [`DecisionMemo`] is included in the [`async_graphql::Context`] of each request, such that identical policy decisions are made once per request, even across a batch
+/// If snapshot reads are configured, a [`Databases::snapshot`] is included in the [`async_graphql::Context`] of each request, such that all of its
+/// queries observe a consistent state of the database
 #[derive(Debug, Clone)]
 pub struct GraphQLHandler<E: Executor> {
     /// The GraphQL executor used to process the request
     executor: E,
+    /// Validates bearer tokens before execution, if set
+    validator: Option<Arc<JwtValidator>>,
+    /// The name of the cookie from which the bearer token is read in the absence of an [`Authorization<Bearer>`] header
+    token_cookie: Option<Arc<str>>,
+    /// The API keys accepted and the header from which they are read, if set
+    api_keys: Option<(Arc<ApiKeys>, HeaderName)>,
+    /// The header from which client credentials service tokens are read, if set
+    service_token_header: Option<HeaderName>,
+    /// The databases of which a snapshot is read by each request, if set
+    snapshots: Option<Databases>,
+    /// The maximum number of operations in a batch, if set
+    max_batch_size: Option<usize>,
+    /// The maximum age for which responses to each named operation may be cached, in place of that of the schema hints
+    operation_max_ages: Arc<HashMap<String, Duration>>,
+}
+
+/// The credentials with which a request was authenticated
+struct Credentials {
+    /// The bearer token, if any
+    token: Option<Authorization<Bearer>>,
+    /// The validated claims of the bearer token, if a [`JwtValidator`] is configured
+    claims: Option<Claims>,
+    /// The machine client making the request, if authenticated by an API key or service token
+    service: Option<ServiceIdentity>,
 }
 
 impl<E: Executor> GraphQLHandler<E> {
     /// Constructs an instance of the handler with the provided schema.
     pub fn new(executor: E) -> Self {
-        Self { executor }
+        Self {
+            executor,
+            validator: None,
+            token_cookie: None,
+            api_keys: None,
+            service_token_header: None,
+            snapshots: None,
+            max_batch_size: None,
+            operation_max_ages: Arc::default(),
+        }
+    }
+
+    /// Validates bearer tokens with the provided [`JwtValidator`] before execution
+    pub fn with_jwt_validator(mut self, validator: Option<Arc<JwtValidator>>) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// Reads the bearer token from the named cookie when no [`Authorization<Bearer>`] header is present
+    pub fn with_token_cookie(mut self, token_cookie: Option<String>) -> Self {
+        self.token_cookie = token_cookie.map(Arc::from);
+        self
+    }
+
+    /// Authenticates requests bearing one of the [`ApiKeys`] in the named header
+    pub fn with_api_keys(mut self, api_keys: Option<Arc<ApiKeys>>, header: HeaderName) -> Self {
+        self.api_keys = api_keys.map(|api_keys| (api_keys, header));
+        self
+    }
+
+    /// Authenticates requests bearing a client credentials token, validated by the [`JwtValidator`], in the named header
+    pub fn with_service_token_header(mut self, header: Option<HeaderName>) -> Self {
+        self.service_token_header = header;
+        self
+    }
+
+    /// Makes all queries of each request within a single snapshot of the [`Databases`]
+    pub fn with_snapshots(mut self, databases: Option<Databases>) -> Self {
+        self.snapshots = databases;
+        self
+    }
+
+    /// Caches responses to the named operations for the provided maximum ages, in place of those of the schema hints
+    pub fn with_operation_max_ages(mut self, max_ages: HashMap<String, Duration>) -> Self {
+        self.operation_max_ages = Arc::new(max_ages);
+        self
+    }
+
+    /// Rejects batches of more than the maximum number of operations
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = Some(max_batch_size);
+        self
+    }
+
+    /// Authenticates a request by its headers, as described on the [`GraphQLHandler`], or responds with the reason it could not be
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Credentials, Response> {
+        let token = self.bearer_token(headers);
+        let claims = match (&self.validator, &token) {
+            (Some(validator), Some(token)) => match validator.validate(token.token()).await {
+                Ok(claims) => Some(claims),
+                Err(err @ JwtError::KeySet(_)) => {
+                    return Err((StatusCode::SERVICE_UNAVAILABLE, err.to_string()).into_response())
+                }
+                Err(err) => {
+                    return Err((
+                        StatusCode::UNAUTHORIZED,
+                        [(WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)],
+                        err.to_string(),
+                    )
+                        .into_response())
+                }
+            },
+            _ => None::<Claims>,
+        };
+        let service = match &self.api_keys {
+            Some((api_keys, header)) => match headers.get(header) {
+                Some(key) => match key.to_str().ok().and_then(|key| api_keys.identify(key)) {
+                    Some(service) => Some(service),
+                    None => {
+                        return Err((StatusCode::UNAUTHORIZED, "Invalid API key").into_response())
+                    }
+                },
+                None => None,
+            },
+            None => None::<ServiceIdentity>,
+        };
+        let service_token = match (&self.validator, &self.service_token_header) {
+            (Some(validator), Some(header)) => headers
+                .get(header)
+                .map(|token| (validator, token.to_str().unwrap_or_default())),
+            _ => None,
+        };
+        let service = match service_token {
+            Some((validator, token)) => match validator.validate(token).await {
+                Ok(claims) => match claims.client_id() {
+                    Some(client_id) => Some(ServiceIdentity(client_id.to_string())),
+                    None => {
+                        return Err((StatusCode::UNAUTHORIZED, "Service token has no client_id")
+                            .into_response())
+                    }
+                },
+                Err(err @ JwtError::KeySet(_)) => {
+                    return Err((StatusCode::SERVICE_UNAVAILABLE, err.to_string()).into_response())
+                }
+                Err(err) => {
+                    return Err((
+                        StatusCode::UNAUTHORIZED,
+                        format!("Invalid service token: {err}"),
+                    )
+                        .into_response())
+                }
+            },
+            None => service,
+        };
+        Ok(Credentials {
+            token,
+            claims,
+            service,
+        })
+    }
+
+    /// The [`VARY`] header of responses which may be cached, naming each header by which requests are authenticated, such
+    /// that shared caches do not serve the response to one client to another
+    fn vary(&self) -> HeaderValue {
+        let headers = [AUTHORIZATION.as_str(), COOKIE.as_str()]
+            .into_iter()
+            .chain(self.api_keys.as_ref().map(|(_, header)| header.as_str()))
+            .chain(self.service_token_header.as_ref().map(HeaderName::as_str))
+            .collect::<Vec<_>>();
+        HeaderValue::from_str(&headers.join(", "))
+            .unwrap_or_else(|_| HeaderValue::from_static("authorization, cookie"))
+    }
+
+    /// Executes a single operation of a request, applying the configured maximum age of the operation
+    async fn execute(&self, request: async_graphql::Request) -> async_graphql::Response {
+        let max_age = if self.operation_max_ages.is_empty() {
+            None
+        } else {
+            self.operation_max_ages
+                .get(&operation_name(&request))
+                .copied()
+        };
+        let mut response = self.executor.execute(request).await;
+        if let Some(max_age) = max_age {
+            response.cache_control.max_age = max_age.as_secs().try_into().unwrap_or(i32::MAX);
+        }
+        response
+    }
+
+    /// The bearer token of the [`Authorization<Bearer>`] header or, if configured and the header is absent, the token cookie
+    fn bearer_token(&self, headers: &HeaderMap) -> Option<Authorization<Bearer>> {
+        headers.typed_get::<Authorization<Bearer>>().or_else(|| {
+            let token_cookie = self.token_cookie.as_ref()?;
+            let cookie = headers.typed_get::<Cookie>()?;
+            Authorization::bearer(cookie.get(token_cookie)?).ok()
+        })
+    }
+
+    /// Serves GraphQL subscriptions over a WebSocket, authenticating the connection exactly like a request of the
+    /// [`Handler`] by the headers of the upgrade request, with the bearer token in the `Authorization` field of its
+    /// `connection_init` payload taking precedence over that of the headers, such that subscriptions are authorized
+    /// exactly like queries
+    ///
+    /// Connections which cannot be authenticated are closed during initialization. Policy decisions are not memoized, as a
+    /// connection may outlive any change to them
+    pub fn subscribe(
+        self,
+        protocol: GraphQLProtocol,
+        upgrade: WebSocketUpgrade,
+        headers: HeaderMap,
+    ) -> Response {
+        upgrade
+            .protocols(ALL_WEBSOCKET_PROTOCOLS)
+            .on_upgrade(move |stream| {
+                GraphQLWebSocket::new(stream, self.executor.clone(), protocol)
+                    .on_connection_init(move |payload| async move {
+                        let mut headers = headers;
+                        if let Some(token) = payload_token(&payload) {
+                            headers.typed_insert(token);
+                        }
+                        let credentials = match self.authenticate(&headers).await {
+                            Ok(credentials) => credentials,
+                            Err(response) => {
+                                return Err(async_graphql::Error::new(
+                                    rejection_message(response).await,
+                                ))
+                            }
+                        };
+                        let mut data = Data::default();
+                        data.insert(credentials.token);
+                        data.insert(credentials.claims);
+                        data.insert(credentials.service);
+                        Ok(data)
+                    })
+                    .serve()
+            })
+    }
+
+    /// Serves a GraphQL operation, typically a subscription, as Server-Sent Events, for clients behind proxies which do not
+    /// support WebSocket upgrades
+    ///
+    /// Requests are authenticated exactly like those of the [`Handler`], and each response is sent as a `next` event followed
+    /// by a `complete` event once the operation ends, as per the distinct connections mode of the GraphQL over SSE protocol.
+    /// Subscriptions may be made over GET, such that browsers may use an `EventSource`, but mutations may not. Policy decisions
+    /// are not memoized, as a stream may outlive any change to them
+    pub async fn stream(self, req: Request) -> Response {
+        let method = req.method().clone();
+        let credentials = match self.authenticate(req.headers()).await {
+            Ok(credentials) => credentials,
+            Err(response) => return response,
+        };
+        let request = match req.extract::<GraphQLRequest, _>().await {
+            Ok(request) => request.into_inner(),
+            Err(err) => return (StatusCode::BAD_REQUEST, err.0.to_string()).into_response(),
+        };
+        if method == Method::GET && operation_type(&request) == Some(OperationType::Mutation) {
+            return (
+                StatusCode::METHOD_NOT_ALLOWED,
+                [(ALLOW, "POST")],
+                Json(json!({
+                    "errors": [{ "message": "Mutations may not be made over GET" }]
+                })),
+            )
+                .into_response();
+        }
+        let request = request
+            .data(credentials.token)
+            .data(credentials.claims)
+            .data(credentials.service);
+        let events = self
+            .executor
+            .execute_stream(request, None)
+            .map(|response| Event::default().event("next").json_data(response))
+            .chain(stream::once(async {
+                Ok(Event::default().event("complete").data(""))
+            }));
+        Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    }
+
+    /// Executes the query, with the variables, on behalf of the caller authenticated by the headers exactly as for a request
+    /// of the [`Handler`], or responds with the reason the caller could not be authenticated
+    pub async fn execute_as(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+        headers: &HeaderMap,
+    ) -> Result<async_graphql::Response, Response> {
+        let credentials = self.authenticate(headers).await?;
+        let request = async_graphql::Request::new(query)
+            .variables(async_graphql::Variables::from_json(variables))
+            .data(credentials.token)
+            .data(credentials.claims)
+            .data(credentials.service)
+            .data(DecisionMemo::default());
+        Ok(self.execute(request).await)
+    }
+
+    /// Serves a single session as JSON, for clients which cannot easily make GraphQL queries, by the proposal, such as
+    /// `cm31111`, and visit number
+    ///
+    /// Requests are authenticated exactly like those of the [`Handler`] and the session is retrieved by the `session` query
+    /// of the schema, such that it is authorized, cached and shaped exactly as though it were queried. Sessions which do not
+    /// exist are [`StatusCode::NOT_FOUND`], and those which are denied [`StatusCode::FORBIDDEN`]
+    pub async fn session(self, proposal: String, visit: u32, headers: HeaderMap) -> Response {
+        let Some((proposal_code, proposal_number)) = parse_proposal(&proposal) else {
+            return invalid_proposal(&proposal);
+        };
+        let variables = json!({
+            "proposalCode": proposal_code,
+            "proposalNumber": proposal_number,
+            "visit": visit,
+        });
+        let response = match self.execute_as(SESSION_QUERY, variables, &headers).await {
+            Ok(response) => response,
+            Err(response) => return response,
+        };
+        if let Some(err) = response.errors.first() {
+            return (
+                error_status(err),
+                Json(json!({ "errors": response.errors })),
+            )
+                .into_response();
+        }
+        let session = response
+            .data
+            .into_json()
+            .map(|mut data| data["session"].take())
+            .unwrap_or_default();
+        if session.is_null() {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(RestErrors::new(format!(
+                    "Session {proposal}-{visit} does not exist"
+                ))),
+            )
+                .into_response();
+        }
+        match serde_json::from_value::<RestSession>(session) {
+            Ok(session) => {
+                with_cache_control(Json(session).into_response(), &response.cache_control)
+            }
+            Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        }
+    }
+
+    /// Serves the sessions the caller is permitted to view, of the proposal if provided, as a table in the [`ExportFormat`]
+    /// with a header row, for pasting into spreadsheets
+    ///
+    /// Requests are authenticated exactly like those of the [`Handler`] and the sessions are retrieved by the `sessions`
+    /// query of the schema, such that exactly those which would be listed by it are exported
+    pub async fn export(
+        self,
+        format: ExportFormat,
+        parameters: ExportParameters,
+        headers: HeaderMap,
+    ) -> Response {
+        let proposal = match &parameters.proposal {
+            Some(proposal) => match parse_proposal(proposal) {
+                Some(proposal) => Some(proposal),
+                None => return invalid_proposal(proposal),
+            },
+            None => None,
+        };
+        let variables = json!({
+            "proposalCode": proposal.map(|(code, _)| code),
+            "proposalNumber": proposal.map(|(_, number)| number),
+            "limit": parameters.limit,
+            "offset": parameters.offset,
+        });
+        let response = match self.execute_as(SESSIONS_QUERY, variables, &headers).await {
+            Ok(response) => response,
+            Err(response) => return response,
+        };
+        if let Some(err) = response.errors.first() {
+            return (
+                error_status(err),
+                Json(json!({ "errors": response.errors })),
+            )
+                .into_response();
+        }
+        let sessions = response
+            .data
+            .into_json()
+            .map(|mut data| data["sessions"].take())
+            .unwrap_or_default();
+        let table = match format.write(sessions.as_array().map(Vec::as_slice).unwrap_or_default()) {
+            Ok(table) => table,
+            Err(err) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+        };
+        let filename = match proposal {
+            Some((code, number)) => format!("sessions-{code}{number}.{}", format.extension()),
+            None => format!("sessions.{}", format.extension()),
+        };
+        with_cache_control(
+            (
+                [
+                    (CONTENT_TYPE, format.content_type().to_string()),
+                    (
+                        CONTENT_DISPOSITION,
+                        format!(r#"attachment; filename="{filename}""#),
+                    ),
+                ],
+                table,
+            )
+                .into_response(),
+            &response.cache_control,
+        )
+    }
+}
+
+/// A Beamline Session, as selected by the `session` query
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RestSession {
+    /// The unique identifier of the session
+    id: u32,
+    /// The visit number of the session within its proposal
+    visit: u32,
+    /// The start of the session, if scheduled
+    #[schema(format = DateTime)]
+    start: Option<String>,
+    /// The end of the session, if scheduled
+    #[schema(format = DateTime)]
+    end: Option<String>,
+    /// The name of the beamline on which the session takes place
+    beamline: Option<String>,
+    /// The proposal of the session
+    proposal: Option<RestProposal>,
+}
+
+/// The Experimental Proposal of a Beamline Session
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RestProposal {
+    /// The code of the proposal, such as `cm`
+    code: Option<String>,
+    /// A unique number identifying the proposal
+    number: Option<u32>,
+    /// The state of the proposal, such as `OPEN`
+    state: Option<String>,
+}
+
+/// The errors with which the REST endpoints respond to requests they cannot serve, as a GraphQL response would list them
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RestErrors {
+    /// The errors, of which there is at least one
+    errors: Vec<RestError>,
+}
+
+impl RestErrors {
+    /// The errors of a request which was not executed, with the message
+    fn new(message: String) -> Self {
+        Self {
+            errors: vec![RestError {
+                message,
+                extensions: None,
+            }],
+        }
+    }
+}
+
+/// An error of a request to a REST endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RestError {
+    /// A description of the error
+    message: String,
+    /// Details of the error, such as the `code` and the `reason` of a policy denial
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    extensions: Option<serde_json::Value>,
+}
+
+/// Serves a single session as JSON
+///
+/// The session is authorized, cached and shaped exactly as though it were retrieved by the `session` query
+#[utoipa::path(
+    get,
+    path = "/sessions/{proposal}/{visit}",
+    tag = "sessions",
+    params(
+        ("proposal" = String, Path, description = "The proposal of the session, such as cm31111"),
+        ("visit" = u32, Path, description = "The visit number of the session within its proposal"),
+    ),
+    responses(
+        (status = 200, description = "The session", body = RestSession),
+        (status = 400, description = "The proposal is not its code followed by its number", body = RestErrors),
+        (status = 401, description = "The credentials of the request are invalid"),
+        (status = 403, description = "The caller is not permitted to view the session", body = RestErrors),
+        (status = 404, description = "The session does not exist", body = RestErrors),
+        (status = 503, description = "Policy decisions are unavailable", body = RestErrors),
+    ),
+    security((), ("bearer" = []), ("api_key" = [])),
+)]
+pub async fn get_session<E: Executor>(
+    State(handler): State<GraphQLHandler<E>>,
+    Path((proposal, visit)): Path<(String, u32)>,
+    headers: HeaderMap,
+) -> Response {
+    handler.session(proposal, visit, headers).await
+}
+
+/// Serves the sessions the caller is permitted to view as CSV, with a header row
+///
+/// Exactly the sessions which would be listed by the `sessions` query are exported
+#[utoipa::path(
+    get,
+    path = "/sessions.csv",
+    tag = "sessions",
+    params(
+        ("proposal" = Option<String>, Query, description = "The proposal, such as cm31111, to whose sessions the export is restricted"),
+        ("limit" = Option<u64>, Query, description = "The greatest number of sessions exported, at most and by default 1000"),
+        ("offset" = Option<u64>, Query, description = "The number of sessions skipped before those exported"),
+    ),
+    responses(
+        (status = 200, description = "The sessions, with a header row", body = String, content_type = "text/csv"),
+        (status = 400, description = "The proposal is not its code followed by its number, or the limit exceeds 1000", body = RestErrors),
+        (status = 401, description = "The credentials of the request are invalid"),
+        (status = 503, description = "Policy decisions are unavailable", body = RestErrors),
+    ),
+    security((), ("bearer" = []), ("api_key" = [])),
+)]
+pub async fn export_csv<E: Executor>(
+    State(handler): State<GraphQLHandler<E>>,
+    Query(parameters): Query<ExportParameters>,
+    headers: HeaderMap,
+) -> Response {
+    handler.export(ExportFormat::Csv, parameters, headers).await
+}
+
+/// Serves the sessions the caller is permitted to view as TSV, with a header row
+///
+/// Exactly the sessions which would be listed by the `sessions` query are exported
+#[utoipa::path(
+    get,
+    path = "/sessions.tsv",
+    tag = "sessions",
+    params(
+        ("proposal" = Option<String>, Query, description = "The proposal, such as cm31111, to whose sessions the export is restricted"),
+        ("limit" = Option<u64>, Query, description = "The greatest number of sessions exported, at most and by default 1000"),
+        ("offset" = Option<u64>, Query, description = "The number of sessions skipped before those exported"),
+    ),
+    responses(
+        (status = 200, description = "The sessions, with a header row", body = String, content_type = "text/tab-separated-values"),
+        (status = 400, description = "The proposal is not its code followed by its number, or the limit exceeds 1000", body = RestErrors),
+        (status = 401, description = "The credentials of the request are invalid"),
+        (status = 503, description = "Policy decisions are unavailable", body = RestErrors),
+    ),
+    security((), ("bearer" = []), ("api_key" = [])),
+)]
+pub async fn export_tsv<E: Executor>(
+    State(handler): State<GraphQLHandler<E>>,
+    Query(parameters): Query<ExportParameters>,
+    headers: HeaderMap,
+) -> Response {
+    handler.export(ExportFormat::Tsv, parameters, headers).await
+}
+
+/// The query parameters of [`GraphQLHandler::export`]
+#[derive(Debug, Deserialize)]
+pub struct ExportParameters {
+    /// The proposal, such as `cm31111`, to whose sessions the export is restricted, if any
+    proposal: Option<String>,
+    /// The greatest number of sessions exported, if not the maximum of the `sessions` query
+    limit: Option<u64>,
+    /// The number of sessions skipped before those exported
+    offset: Option<u64>,
+}
+
+/// A delimited text format in which sessions are exported by [`GraphQLHandler::export`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values, as per RFC 4180
+    Csv,
+    /// Tab-separated values
+    Tsv,
+}
+
+impl ExportFormat {
+    /// The columns of the table, in order, as the header row
+    const COLUMNS: [&'static str; 7] = [
+        "id", "proposal", "visit", "beamline", "start", "end", "state",
+    ];
+
+    /// The delimiter between the fields of each row
+    fn delimiter(&self) -> u8 {
+        match self {
+            ExportFormat::Csv => b',',
+            ExportFormat::Tsv => b'\t',
+        }
+    }
+
+    /// The extension of files of the format
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+        }
+    }
+
+    /// The media type of the format
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Tsv => "text/tab-separated-values; charset=utf-8",
+        }
+    }
+
+    /// Writes the sessions, as selected by the `sessions` query of the export, as a table with a header row
+    fn write(&self, sessions: &[serde_json::Value]) -> Result<Vec<u8>, csv::Error> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter())
+            .from_writer(Vec::new());
+        writer.write_record(Self::COLUMNS)?;
+        for session in sessions {
+            let proposal = &session["proposal"];
+            let field = |value: &serde_json::Value| match value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            writer.write_record([
+                field(&session["id"]),
+                format!("{}{}", field(&proposal["code"]), field(&proposal["number"])),
+                field(&session["visit"]),
+                field(&session["beamline"]),
+                field(&session["start"]),
+                field(&session["end"]),
+                field(&proposal["state"]),
+            ])?;
+        }
+        writer
+            .into_inner()
+            .map_err(|err| csv::Error::from(err.into_error()))
     }
 }
 
+/// Rejects a proposal which is not its code followed by its number
+fn invalid_proposal(proposal: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(RestErrors::new(format!(
+            "Invalid proposal {proposal}, expected its code and number such as cm31111"
+        ))),
+    )
+        .into_response()
+}
+
+/// The status with which a REST endpoint responds to an error of its query, by the code of the error
+pub fn error_status(err: &async_graphql::ServerError) -> StatusCode {
+    match err
+        .extensions
+        .as_ref()
+        .and_then(|extensions| extensions.get("code"))
+    {
+        Some(async_graphql::Value::String(code)) if code == "FORBIDDEN" => StatusCode::FORBIDDEN,
+        Some(async_graphql::Value::String(code)) if code == "BAD_USER_INPUT" => {
+            StatusCode::BAD_REQUEST
+        }
+        Some(async_graphql::Value::String(code)) if code.starts_with("OPA_") => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Sets the `Cache-Control` header of a REST response by the cache control hints of its query
+fn with_cache_control(mut response: Response, cache_control: &CacheControl) -> Response {
+    if let Some(value) = cache_control
+        .value()
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+    {
+        response.headers_mut().insert(CACHE_CONTROL, value);
+    }
+    response
+}
+
+/// Splits a proposal, such as `cm31111`, into its code and number
+fn parse_proposal(proposal: &str) -> Option<(&str, u32)> {
+    let split = proposal.find(|c: char| c.is_ascii_digit())?;
+    let (code, number) = proposal.split_at(split);
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((code, number.parse().ok()?))
+}
+
+/// The body of the response with which an unauthenticated request is rejected, as the reason for the rejection
+pub async fn rejection_message(response: Response) -> String {
+    axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .map(|body| String::from_utf8_lossy(&body).into_owned())
+        .unwrap_or_default()
+}
+
+/// The bearer token in the `Authorization` field, matched case insensitively, of a WebSocket `connection_init` payload
+fn payload_token(payload: &serde_json::Value) -> Option<Authorization<Bearer>> {
+    let (_, value) = payload
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("authorization"))?;
+    let value = value.as_str()?;
+    Authorization::bearer(value.strip_prefix("Bearer ").unwrap_or(value)).ok()
+}
+
 impl<S, E> Handler<((),), S> for GraphQLHandler<E>
 where
     E: Executor,
 {
     type Future = Pin<Box<dyn Future<Output = Response> + Send + 'static>>;
 
-    fn call(self, mut req: Request, _state: S) -> Self::Future {
+    fn call(self, req: Request, _state: S) -> Self::Future {
         Box::pin(async move {
-            let token = req
-                .extract_parts::<TypedHeader<Authorization<Bearer>>>()
-                .await
-                .ok()
-                .map(|token| token.0);
-            let request = req.extract::<GraphQLRequest, _>().await;
-            match request {
-                Ok(request) => GraphQLResponse::from(
-                    self.executor
-                        .execute(request.into_inner().data(token))
-                        .await,
+            let method = req.method().clone();
+            let if_none_match = req.headers().typed_get::<IfNoneMatch>();
+            let credentials = match self.authenticate(req.headers()).await {
+                Ok(credentials) => credentials,
+                Err(response) => return response,
+            };
+            let batch = match req.extract::<GraphQLBatchRequest, _>().await {
+                Ok(batch) => batch.into_inner(),
+                Err(err) => return (StatusCode::BAD_REQUEST, err.0.to_string()).into_response(),
+            };
+            if method == Method::GET
+                && batch.iter().any(|request| {
+                    operation_type(request).is_some_and(|ty| ty != OperationType::Query)
+                })
+            {
+                return (
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    [(ALLOW, "POST")],
+                    Json(json!({
+                        "errors": [{ "message": "Only query operations may be made over GET" }]
+                    })),
                 )
-                .into_response(),
-                Err(err) => (StatusCode::BAD_REQUEST, err.0.to_string()).into_response(),
+                    .into_response();
+            }
+            if let (BatchRequest::Batch(requests), Some(max_batch_size)) =
+                (&batch, self.max_batch_size)
+            {
+                if requests.len() > max_batch_size {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({
+                            "errors": [{ "message": format!("Batch exceeds the limit of {max_batch_size} operations") }]
+                        })),
+                    )
+                        .into_response();
+                }
+            }
+            let mut batch = batch
+                .data(credentials.token)
+                .data(credentials.claims)
+                .data(credentials.service)
+                .data(DecisionMemo::default());
+            let snapshot = self.snapshots.as_ref().map(Databases::snapshot);
+            if let Some(snapshot) = &snapshot {
+                batch = batch.data(snapshot.clone());
+            }
+            let response = match batch {
+                BatchRequest::Single(request) => BatchResponse::Single(self.execute(request).await),
+                BatchRequest::Batch(requests) => BatchResponse::Batch(
+                    join_all(requests.into_iter().map(|request| self.execute(request))).await,
+                ),
+            };
+            if let Some(snapshot) = snapshot {
+                snapshot.end_snapshot().await;
+            }
+            let mut response = GraphQLResponse::from(response).into_response();
+            if method == Method::GET {
+                response.headers_mut().insert(VARY, self.vary());
+                response = with_entity_tag(response, if_none_match).await;
             }
+            response
         })
     }
 }
+
+/// Tags the response with an [`ETag`] computed over its body, replacing it with [`StatusCode::NOT_MODIFIED`] if the tag
+/// matches the [`IfNoneMatch`] of the request, such that clients and intermediary caches may revalidate cached responses
+/// without transferring them again
+async fn with_entity_tag(response: Response, if_none_match: Option<IfNoneMatch>) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let digest = Sha256::digest(&body);
+    let Ok(etag) = format!("\"{}\"", hex::encode(&digest[..16])).parse::<ETag>() else {
+        return Response::from_parts(parts, Body::from(body));
+    };
+    parts.headers.typed_insert(etag.clone());
+    if if_none_match.is_some_and(|if_none_match| !if_none_match.precondition_passes(&etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(CONTENT_TYPE);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// The name of the operation of the request, as specified or otherwise that of the only operation of its document, or empty
+/// if the operation is anonymous
+fn operation_name(request: &async_graphql::Request) -> String {
+    if let Some(operation_name) = &request.operation_name {
+        return operation_name.clone();
+    }
+    match async_graphql::parser::parse_query(&request.query).map(|document| document.operations) {
+        Ok(DocumentOperations::Multiple(operations)) if operations.len() == 1 => operations
+            .into_keys()
+            .next()
+            .map_or_else(String::new, |name| name.to_string()),
+        _ => String::new(),
+    }
+}
+
+/// The type of the operation of the request, unless it cannot be determined as the request does not parse
+fn operation_type(request: &async_graphql::Request) -> Option<OperationType> {
+    let document = async_graphql::parser::parse_query(&request.query).ok()?;
+    let operation = match (&document.operations, &request.operation_name) {
+        (DocumentOperations::Single(operation), _) => Some(operation),
+        (DocumentOperations::Multiple(operations), Some(name)) => operations.get(name.as_str()),
+        (DocumentOperations::Multiple(_), None) => None,
+    };
+    operation.map(|operation| operation.node.ty)
+}
+
+/// Whether the request supplies a GraphQL query, or the extensions of a persisted query, in its URI query parameters
+pub fn has_query_parameter(request: &Request) -> bool {
+    request.uri().query().is_some_and(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .any(|(key, _)| key == "query" || key == "extensions")
+    })
+}
+
+/// Responds to requests shed while the maximum number of requests are in flight with [`StatusCode::SERVICE_UNAVAILABLE`], a
+/// `Retry-After` header and a GraphQL error
+pub async fn shed_load(err: BoxError) -> Response {
+    if !err.is::<Overloaded>() {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "errors": [{ "message": err.to_string() }] })),
+        )
+            .into_response();
+    }
+    warn!("Shed request as too many requests are in flight");
+    Metrics::get().shed_requests.add(1, &[]);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, "1")],
+        Json(json!({ "errors": [{ "message": "Too many requests are in flight" }] })),
+    )
+        .into_response()
+}
+
+/// Cancels requests which do not complete within the timeout, responding with [`StatusCode::GATEWAY_TIMEOUT`] and a GraphQL error
+pub async fn limit_duration(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("Request did not complete within {timeout:?}");
+            Metrics::get().request_timeouts.add(1, &[]);
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(json!({
+                    "errors": [{ "message": format!("Request did not complete within {timeout:?}") }]
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Rejects requests with bodies larger than the limit, in bytes, with [`StatusCode::PAYLOAD_TOO_LARGE`] and a GraphQL error
+///
+/// The body is buffered up to the limit, such that oversized payloads are rejected without being read into memory in full
+pub async fn limit_body_size(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    match axum::body::to_bytes(body, limit).await {
+        Ok(body) => next.run(Request::from_parts(parts, Body::from(body))).await,
+        Err(_) => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({
+                "errors": [{ "message": format!("Request body exceeds the limit of {limit} bytes") }]
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Records the duration of each HTTP request, by method, route and response status
+pub async fn record_http_metrics(
+    route: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    Metrics::get().http_server_request_duration_seconds.record(
+        start.elapsed().as_secs_f64(),
+        &[
+            KeyValue::new("method", method),
+            KeyValue::new(
+                "route",
+                route.as_ref().map_or("", MatchedPath::as_str).to_string(),
+            ),
+            KeyValue::new("status", i64::from(response.status().as_u16())),
+        ],
+    );
+    response
+}
+
+/// Responds with the metrics of the Prometheus registry, in the Prometheus text exposition format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "probes",
+    responses((status = 200, description = "The metrics", body = String, content_type = "text/plain")),
+)]
+pub async fn metrics(State(registry): State<Registry>) -> Response {
+    match TextEncoder::new().encode_to_string(&registry.gather()) {
+        Ok(metrics) => ([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], metrics).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Responds with `200 OK` whilst the service is able to handle requests
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "probes",
+    responses((status = 200, description = "The service is alive", body = String, content_type = "text/plain")),
+)]
+pub async fn liveness() -> Response {
+    (StatusCode::OK, "Alive").into_response()
+}
+
+/// Responds with `200 OK` if the database is reachable and the Open Policy Agent is ready to make decisions, otherwise `503 Service Unavailable`
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "probes",
+    responses(
+        (status = 200, description = "The service is ready", body = String, content_type = "text/plain"),
+        (status = 503, description = "The database or the Open Policy Agent is unavailable", body = String, content_type = "text/plain"),
+    ),
+)]
+pub async fn readiness(State((opa_client, database)): State<(OpaClient, Databases)>) -> Response {
+    if let Err(err) = database.ping().await {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("Database unreachable: {err}"),
+        )
+            .into_response();
+    }
+    match opa_client.ready().await {
+        Ok(()) => (StatusCode::OK, "Ready").into_response(),
+        Err(err) => (StatusCode::SERVICE_UNAVAILABLE, err.to_string()).into_response(),
+    }
+}
+
+/// Responds with the level at which logs are currently emitted
+#[utoipa::path(
+    get,
+    path = "/admin/log-level",
+    tag = "admin",
+    responses((status = 200, description = "The current level, such as info", body = String, content_type = "text/plain")),
+)]
+pub async fn get_log_level(State(log_level): State<LogLevel>) -> Response {
+    match log_level.current() {
+        Ok(level) => (StatusCode::OK, level.to_string()).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Sets the level at which logs are emitted to that named in the body of the request, such as `debug`, responding with
+/// `400 Bad Request` if it is not a level
+#[utoipa::path(
+    post,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body(content = String, description = "The level, such as debug", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "The level which was set", body = String, content_type = "text/plain"),
+        (status = 400, description = "The body is not a level", body = String, content_type = "text/plain"),
+    ),
+)]
+pub async fn set_log_level(State(log_level): State<LogLevel>, body: String) -> Response {
+    let level = match body.trim().parse::<tracing::Level>() {
+        Ok(level) => level,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    match log_level.set(level) {
+        Ok(()) => (
+            StatusCode::OK,
+            tracing::level_filters::LevelFilter::from_level(level).to_string(),
+        )
+            .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+tokio::task_local! {
+    /// Set whilst a probe request is being handled
+    static PROBING: ();
+}
+
+/// Handles the request as a probe, such that no spans or events are emitted whilst it is handled
+pub async fn quieten_probe(request: Request, next: Next) -> Response {
+    PROBING.scope((), next.run(request)).await
+}
+
+/// Whether the current task is handling a probe request, in which case its spans and events are discarded
+pub fn is_probing() -> bool {
+    PROBING.try_with(|_| ()).is_ok()
+}
+
+/// Properties of the parsing of proposals, which are given in the paths of requests
+#[cfg(test)]
+mod tests {
+    use super::parse_proposal;
+    use proptest::prelude::*;
+
+    /// Codes of proposals at the facility, including those of beamtime allocation groups and of commissioning
+    const PROPOSAL_CODES: [&str; 8] = ["mx", "cm", "in", "nt", "nr", "sw", "bi", "ee"];
+
+    proptest! {
+        #[test]
+        fn proposal_round_trips(code in "[a-zA-Z]{1,4}", number: u32) {
+            let proposal = format!("{code}{number}");
+            prop_assert_eq!(parse_proposal(&proposal), Some((code.as_str(), number)));
+        }
+
+        #[test]
+        fn facility_proposal_round_trips(code in proptest::sample::select(&PROPOSAL_CODES[..]), number in 1..100_000_u32) {
+            let proposal = format!("{code}{number}");
+            prop_assert_eq!(parse_proposal(&proposal), Some((code, number)));
+        }
+
+        #[test]
+        fn zero_padded_number_is_parsed(code in "[a-z]{2}", number: u32, padding in 1..4_usize) {
+            let proposal = format!("{code}{}{number}", "0".repeat(padding));
+            prop_assert_eq!(parse_proposal(&proposal), Some((code.as_str(), number)));
+        }
+
+        #[test]
+        fn overflowing_number_is_rejected(code in "[a-z]{2}", number in u64::from(u32::MAX) + 1..=u64::MAX) {
+            let proposal = format!("{code}{number}");
+            prop_assert_eq!(parse_proposal(&proposal), None);
+        }
+
+        #[test]
+        fn visit_is_not_a_proposal(code in "[a-z]{2}", number: u32, visit: u32) {
+            let proposal = format!("{code}{number}-{visit}");
+            prop_assert_eq!(parse_proposal(&proposal), None);
+        }
+
+        #[test]
+        fn proposal_without_code_is_rejected(number: u32) {
+            let proposal = number.to_string();
+            prop_assert_eq!(parse_proposal(&proposal), None);
+        }
+
+        #[test]
+        fn proposal_without_number_is_rejected(code in "\\PN*") {
+            prop_assert_eq!(parse_proposal(&code), None);
+        }
+
+        #[test]
+        fn non_ascii_code_is_rejected(code in "[a-z]*[^\\x00-\\x7F][a-z]*", number: u32) {
+            let proposal = format!("{code}{number}");
+            prop_assert_eq!(parse_proposal(&proposal), None);
+        }
+
+        #[test]
+        fn parsed_proposal_is_prefixed_by_its_code(proposal: String) {
+            if let Some((code, _)) = parse_proposal(&proposal) {
+                prop_assert!(!code.is_empty());
+                prop_assert!(code.chars().all(|c| c.is_ascii_alphabetic()));
+                prop_assert!(proposal.starts_with(code));
+            }
+        }
+    }
+}
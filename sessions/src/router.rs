@@ -0,0 +1,208 @@
+use crate::{
+    database::Databases,
+    graphql::RootSchema,
+    log_level::LogLevel,
+    opa::OpaClient,
+    rate_limit::{limit_rate, RateLimiter},
+    route_handlers::{
+        export_csv, export_tsv, get_log_level, get_session, has_query_parameter, limit_body_size,
+        limit_duration, liveness, metrics, quieten_probe, readiness, record_http_metrics,
+        set_log_level, shed_load, GraphQLHandler,
+    },
+};
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::GraphQLProtocol;
+use axum::{
+    error_handling::HandleErrorLayer,
+    extract::{ws::WebSocketUpgrade, Request},
+    handler::Handler,
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use axum_tracing_opentelemetry::middleware::{OtelAxumLayer, OtelInResponseLayer};
+use std::time::Duration;
+use tower::{limit::ConcurrencyLimitLayer, load_shed::LoadShedLayer, ServiceBuilder};
+use tower_http::cors::CorsLayer;
+
+/// Creates an [`axum::Router`] serving GraphiQL, if enabled, synchronous GraphQL over GET and POST and GraphQL subscriptions
+///
+/// GraphiQL and the GraphQL endpoint are served at the endpoint path, subscriptions over WebSocket at `/ws` and over
+/// Server-Sent Events at `/sse` beneath it, single sessions as JSON at `/sessions/{proposal}/{visit}` beneath it, and
+/// lists of sessions as CSV and TSV at `/sessions.csv` and `/sessions.tsv` beneath it
+///
+/// The GraphQL endpoint enforces the Cross-Origin Resource Sharing policy of the [`CorsLayer`], if provided, and rejects bodies
+/// larger than the maximum body size, requests not completed within the request timeout, requests beyond the maximum number in
+/// flight and requests from clients exceeding the rate of the [`RateLimiter`], if provided
+#[allow(clippy::too_many_arguments)]
+pub fn setup_router(
+    handler: GraphQLHandler<RootSchema>,
+    endpoint_path: &str,
+    cors: Option<CorsLayer>,
+    graphiql: bool,
+    max_body_size: usize,
+    request_timeout: Duration,
+    max_concurrent_requests: Option<usize>,
+    rate_limiter: Option<RateLimiter>,
+) -> Router {
+    #[allow(clippy::missing_docs_in_private_items)]
+    const SUBSCRIPTION_ENDPOINT: &str = "/ws";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const EVENT_STREAM_ENDPOINT: &str = "/sse";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const SESSION_ENDPOINT: &str = "/sessions/:proposal/:visit";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const CSV_EXPORT_ENDPOINT: &str = "/sessions.csv";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const TSV_EXPORT_ENDPOINT: &str = "/sessions.tsv";
+
+    let beneath_endpoint = |path: &str| match endpoint_path {
+        "/" => path.to_string(),
+        endpoint_path => format!("{endpoint_path}{path}"),
+    };
+    let subscription_path = beneath_endpoint(SUBSCRIPTION_ENDPOINT);
+    let event_stream_path = beneath_endpoint(EVENT_STREAM_ENDPOINT);
+    let session_path = beneath_endpoint(SESSION_ENDPOINT);
+
+    let router =
+        Router::new()
+            .route(
+                endpoint_path,
+                get({
+                    let handler = handler.clone();
+                    let graphiql = graphiql.then(|| {
+                        GraphiQLSource::build()
+                            .endpoint(endpoint_path)
+                            .subscription_endpoint(&subscription_path)
+                            .finish()
+                    });
+                    move |request: Request| async move {
+                        if has_query_parameter(&request) {
+                            handler.call(request, ()).await
+                        } else if let Some(graphiql) = graphiql {
+                            Html(graphiql).into_response()
+                        } else {
+                            StatusCode::NOT_FOUND.into_response()
+                        }
+                    }
+                })
+                .post(handler.clone()),
+            )
+            .route(
+                &event_stream_path,
+                get({
+                    let handler = handler.clone();
+                    move |request: Request| handler.stream(request)
+                })
+                .post({
+                    let handler = handler.clone();
+                    move |request: Request| handler.stream(request)
+                }),
+            )
+            .route(&session_path, get(get_session).with_state(handler.clone()))
+            .route(
+                &beneath_endpoint(CSV_EXPORT_ENDPOINT),
+                get(export_csv).with_state(handler.clone()),
+            )
+            .route(
+                &beneath_endpoint(TSV_EXPORT_ENDPOINT),
+                get(export_tsv).with_state(handler.clone()),
+            )
+            .route(
+                &subscription_path,
+                get(
+                    move |protocol: GraphQLProtocol,
+                          upgrade: WebSocketUpgrade,
+                          headers: HeaderMap| async move {
+                        handler.subscribe(protocol, upgrade, headers)
+                    },
+                ),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                max_body_size,
+                limit_body_size,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                request_timeout,
+                limit_duration,
+            ));
+    let router = match max_concurrent_requests {
+        Some(max_concurrent_requests) => router.layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(shed_load))
+                .layer(LoadShedLayer::new())
+                .layer(ConcurrencyLimitLayer::new(max_concurrent_requests)),
+        ),
+        None => router,
+    };
+    let router = match rate_limiter {
+        Some(rate_limiter) => router.layer(axum::middleware::from_fn_with_state(
+            rate_limiter,
+            limit_rate,
+        )),
+        None => router,
+    };
+    let router = match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router
+        .layer(axum::middleware::from_fn(record_http_metrics))
+        .layer(OtelInResponseLayer)
+        .layer(OtelAxumLayer::default())
+}
+
+/// Creates an [`axum::Router`] serving the liveness and readiness probes, the Prometheus metrics if a registry is provided, and
+/// the log level endpoint if a [`LogLevel`] is provided
+///
+/// The log level should only be provided when the router is served on a separate listener, as it is otherwise exposed to
+/// clients of the API
+///
+/// These are routed without the OpenTelemetry layers, such that frequent polling does not skew request traces and metrics, and
+/// the probes emit no spans or logs
+pub fn setup_admin_router(
+    opa_client: OpaClient,
+    database: Databases,
+    prometheus_registry: Option<prometheus::Registry>,
+    log_level: Option<LogLevel>,
+) -> Router {
+    #[allow(clippy::missing_docs_in_private_items)]
+    const LIVENESS_ENDPOINT: &str = "/healthz";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const READINESS_ENDPOINT: &str = "/readyz";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const METRICS_ENDPOINT: &str = "/metrics";
+    #[allow(clippy::missing_docs_in_private_items)]
+    const LOG_LEVEL_ENDPOINT: &str = "/admin/log-level";
+
+    let router = Router::new()
+        .route(LIVENESS_ENDPOINT, get(liveness))
+        .route(
+            READINESS_ENDPOINT,
+            get(readiness).with_state((opa_client, database)),
+        )
+        .route_layer(axum::middleware::from_fn(quieten_probe));
+    let router = match prometheus_registry {
+        Some(registry) => router.route(METRICS_ENDPOINT, get(metrics).with_state(registry)),
+        None => router,
+    };
+    match log_level {
+        Some(handle) => router.route(
+            LOG_LEVEL_ENDPOINT,
+            get(get_log_level).post(set_log_level).with_state(handle),
+        ),
+        None => router,
+    }
+}
+
+/// Creates an [`axum::Router`] serving the OpenAPI document of the routes other than GraphQL, such that clients of them may
+/// be generated
+///
+/// This is routed without the OpenTelemetry layers, as it is static
+pub fn setup_openapi_router(document: utoipa::openapi::OpenApi) -> Router {
+    #[allow(clippy::missing_docs_in_private_items)]
+    const OPENAPI_ENDPOINT: &str = "/openapi.json";
+
+    Router::new().route(OPENAPI_ENDPOINT, get(move || async move { Json(document) }))
+}
@@ -0,0 +1,86 @@
+use opentelemetry::{metrics::AsyncInstrument, KeyValue};
+use tokio::runtime::Handle;
+
+/// Exports gauges of the scheduling queues and blocking pool of the [`tokio`] runtime, and counters of the time each worker
+/// has spent busy, such that latency caused by a saturated runtime can be told apart from that of its dependencies
+///
+/// The runtime metrics are only available when compiled with `--cfg tokio_unstable`, as configured in `.cargo/config.toml`,
+/// so this is omitted from builds which set their own `RUSTFLAGS` and from those of dependent crates
+pub fn export_runtime_metrics(runtime: Handle) {
+    let meter = opentelemetry::global::meter(crate::built_info::PKG_NAME);
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_workers")
+        .with_description("The number of worker threads of the runtime")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(handle.metrics().num_workers() as u64, &[]);
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .f64_observable_counter("tokio_worker_busy_duration_seconds")
+        .with_description("The total time the worker thread has spent executing tasks")
+        .with_callback(move |observer: &dyn AsyncInstrument<f64>| {
+            let metrics = handle.metrics();
+            for worker in 0..metrics.num_workers() {
+                observer.observe(
+                    metrics.worker_total_busy_duration(worker).as_secs_f64(),
+                    &[KeyValue::new("worker", worker as i64)],
+                );
+            }
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_active_tasks")
+        .with_description("The number of tasks alive in the runtime")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(handle.metrics().active_tasks_count() as u64, &[]);
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_global_queue_depth")
+        .with_description("The number of tasks waiting in the queue shared by the worker threads")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(handle.metrics().injection_queue_depth() as u64, &[]);
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_worker_local_queue_depth")
+        .with_description("The number of tasks waiting in the local queue of the worker thread")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            let metrics = handle.metrics();
+            for worker in 0..metrics.num_workers() {
+                observer.observe(
+                    metrics.worker_local_queue_depth(worker) as u64,
+                    &[KeyValue::new("worker", worker as i64)],
+                );
+            }
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_blocking_threads")
+        .with_description("The number of threads in the blocking pool")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(handle.metrics().num_blocking_threads() as u64, &[]);
+        })
+        .init();
+    let handle = runtime.clone();
+    meter
+        .u64_observable_gauge("tokio_idle_blocking_threads")
+        .with_description("The number of idle threads in the blocking pool")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(handle.metrics().num_idle_blocking_threads() as u64, &[]);
+        })
+        .init();
+    meter
+        .u64_observable_gauge("tokio_blocking_queue_depth")
+        .with_description("The number of tasks waiting for a thread in the blocking pool")
+        .with_callback(move |observer: &dyn AsyncInstrument<u64>| {
+            observer.observe(runtime.metrics().blocking_queue_depth() as u64, &[]);
+        })
+        .init();
+}
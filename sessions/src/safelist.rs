@@ -0,0 +1,122 @@
+use crate::route_handlers::{SESSIONS_QUERY, SESSION_QUERY};
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest},
+    parser::{parse_query, types::ExecutableDocument},
+    Request, ServerError, ServerResult, Value,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path, sync::Arc};
+use tracing::{info, warn};
+
+/// The operations executed by the REST, CSV and gRPC APIs, which are permitted in addition to those in the manifest
+const BUILT_IN_OPERATIONS: [&str; 2] = [SESSION_QUERY, SESSIONS_QUERY];
+
+/// A persisted query manifest, in the format produced by the Apollo client tooling
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    /// The operations which may be executed
+    operations: Vec<ManifestOperation>,
+}
+
+/// An operation in the persisted query manifest
+#[derive(Debug, Deserialize)]
+struct ManifestOperation {
+    /// The identifier by which clients refer to the operation
+    id: String,
+    /// The GraphQL document of the operation
+    body: String,
+}
+
+/// The operations permitted in safelisting mode, keyed by their manifest identifier
+///
+/// Operations are also recognised by the SHA-256 digest of their document, such that clients may send the full document
+/// or an Automatic Persisted Query hash in place of the identifier
+#[derive(Debug, Clone)]
+pub struct Safelist {
+    /// The parsed documents of the permitted operations, keyed by their manifest identifier
+    documents: Arc<HashMap<String, ExecutableDocument>>,
+    /// The manifest identifiers of the permitted operations, keyed by the hex encoded SHA-256 digest of their document
+    digests: Arc<HashMap<String, String>>,
+}
+
+impl Safelist {
+    /// Loads the permitted operations from a JSON persisted query manifest containing a list of `id` and `body` pairs
+    ///
+    /// The `BUILT_IN_OPERATIONS` are also permitted, identified by the digest of their document
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let manifest: Manifest = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        info!(
+            "Loaded {} safelisted operations from {}",
+            manifest.operations.len(),
+            path.display()
+        );
+        let built_in = BUILT_IN_OPERATIONS.map(|body| ManifestOperation {
+            id: format!("{:x}", Sha256::digest(body.as_bytes())),
+            body: body.to_string(),
+        });
+        let mut documents = HashMap::new();
+        let mut digests = HashMap::new();
+        for operation in manifest.operations.into_iter().chain(built_in) {
+            let document = parse_query(&operation.body)
+                .map_err(|err| anyhow::anyhow!("Invalid operation {}: {err}", operation.id))?;
+            digests.insert(
+                format!("{:x}", Sha256::digest(operation.body.as_bytes())),
+                operation.id.clone(),
+            );
+            documents.insert(operation.id, document);
+        }
+        Ok(Self {
+            documents: Arc::new(documents),
+            digests: Arc::new(digests),
+        })
+    }
+
+    /// Finds the document of the permitted operation with the manifest identifier or document digest, if any
+    fn find(&self, key: &str) -> Option<&ExecutableDocument> {
+        self.documents
+            .get(key)
+            .or_else(|| self.digests.get(key).and_then(|id| self.documents.get(id)))
+    }
+
+    /// Finds the document of the permitted operation referred to by the request, by the identifier or hash in its
+    /// `persistedQuery` extension or otherwise by the digest of its document
+    fn resolve(&self, request: &mut Request) -> ServerResult<ExecutableDocument> {
+        let key = match request.extensions.remove("persistedQuery") {
+            Some(Value::Object(persisted_query)) => match persisted_query
+                .get("id")
+                .or_else(|| persisted_query.get("sha256Hash"))
+            {
+                Some(Value::String(key)) => key.clone(),
+                _ => return Err(ServerError::new("Invalid persistedQuery extension", None)),
+            },
+            Some(_) => return Err(ServerError::new("Invalid persistedQuery extension", None)),
+            None => format!("{:x}", Sha256::digest(request.query.as_bytes())),
+        };
+        self.find(&key).cloned().ok_or_else(|| {
+            warn!("Rejected operation absent from the safelist");
+            ServerError::new("Operation is not in the safelist", None)
+        })
+    }
+}
+
+impl ExtensionFactory for Safelist {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(self.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for Safelist {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let document = self.resolve(&mut request)?;
+        request.query = String::new();
+        request.set_parsed_query(document);
+        next.run(ctx, request).await
+    }
+}
@@ -0,0 +1,66 @@
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo},
+    ServerResult, Value,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// An [`ExtensionFactory`] logging a warning, also recorded as an event of the current span, whenever a resolver takes
+/// longer than the threshold
+///
+/// The field path and the names of its arguments are logged, but argument values are redacted as they may identify users
+#[derive(Debug, Clone, Copy)]
+pub struct SlowResolvers {
+    /// The duration beyond which a resolver is considered slow
+    threshold: Duration,
+}
+
+impl SlowResolvers {
+    /// Creates a [`SlowResolvers`] warning of resolvers taking longer than the threshold
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+}
+
+impl ExtensionFactory for SlowResolvers {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(*self)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for SlowResolvers {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+        let (path, field) = (info.path_node, info.field);
+        let start = Instant::now();
+        let result = next.run(ctx, info).await;
+        let elapsed = start.elapsed();
+        if elapsed > self.threshold {
+            let arguments = field
+                .arguments
+                .iter()
+                .map(|(name, _)| format!("{}: <redacted>", name.node))
+                .collect::<Vec<_>>()
+                .join(", ");
+            warn!(
+                path = path.to_string(),
+                arguments,
+                duration_seconds = elapsed.as_secs_f64(),
+                "Resolver exceeded {:?}",
+                self.threshold
+            );
+        }
+        result
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::Display;
+
+/// An error which prevents the server from starting, or from continuing to serve
+#[derive(Debug)]
+pub enum StartupError {
+    /// A configuration file, environment file or argument could not be read or is invalid
+    Config(anyhow::Error),
+    /// The logging, tracing or metrics pipelines could not be set up
+    Telemetry(anyhow::Error),
+    /// The database could not be connected to, or the development database could not be seeded
+    Database(anyhow::Error),
+    /// The database is missing tables or columns of the entities
+    Schema(anyhow::Error),
+    /// The Open Policy Agent could not be reached, or has not activated its bundles
+    Policy(anyhow::Error),
+    /// A TLS certificate, key or CA bundle, of the server or of the Open Policy Agent client, could not be loaded
+    Tls(anyhow::Error),
+    /// A socket could not be bound
+    Bind(anyhow::Error),
+    /// The server failed whilst serving requests
+    Serve(std::io::Error),
+}
+
+impl StartupError {
+    /// The status with which the process exits, following the conventions of `sysexits.h`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            StartupError::Config(_) => 78,
+            StartupError::Telemetry(_) => 70,
+            StartupError::Database(_) => 69,
+            StartupError::Schema(_) => 65,
+            StartupError::Policy(_) => 69,
+            StartupError::Tls(_) => 66,
+            StartupError::Bind(_) => 71,
+            StartupError::Serve(_) => 74,
+        }
+    }
+
+    /// A suggestion of what the operator should check to resolve the error
+    pub fn hint(&self) -> &'static str {
+        match self {
+            StartupError::Config(_) => {
+                "Check the flags, environment variables and configuration file against `sessions help <command>`, or inspect those of `sessions serve` with `sessions config print`"
+            }
+            StartupError::Telemetry(_) => {
+                "Check OTEL_COLLECTOR_URL is a valid URL and AUDIT_LOG is a writable path"
+            }
+            StartupError::Database(_) => {
+                "Check DATABASE_URL and DATABASE_REPLICA_URLS, that the database is reachable from this host and that the credentials are valid, without write grants if DB_READ_ONLY is set"
+            }
+            StartupError::Schema(_) => {
+                "Check the database is of a supported ISPyB release, or pass --db-skip-schema-check to serve regardless"
+            }
+            StartupError::Policy(_) => {
+                "Check OPA_URL, that the Open Policy Agent is reachable from this host and that its bundles are activated"
+            }
+            StartupError::Tls(_) => {
+                "Check TLS_CERT, TLS_KEY, OPA_CLIENT_CERT, OPA_CLIENT_KEY and OPA_CA_BUNDLE name readable PEM encoded files"
+            }
+            StartupError::Bind(_) => {
+                "Check PORT, LISTEN and ADMIN_LISTEN are not in use and that the process is permitted to bind them"
+            }
+            StartupError::Serve(_) => "Check the logs preceding the failure for its cause",
+        }
+    }
+
+    /// Writes the error and its hint to stderr and exits the process with its [`StartupError::exit_code`]
+    pub fn exit(&self) -> ! {
+        eprintln!("Fatal: {self}");
+        eprintln!("Hint: {}", self.hint());
+        std::process::exit(self.exit_code())
+    }
+}
+
+impl Display for StartupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartupError::Config(err) => write!(f, "Invalid configuration: {err:#}"),
+            StartupError::Telemetry(err) => write!(f, "Could not set up telemetry: {err:#}"),
+            StartupError::Database(err) => write!(f, "Could not connect to database: {err:#}"),
+            StartupError::Schema(err) => write!(f, "Incompatible database schema: {err:#}"),
+            StartupError::Policy(err) => write!(f, "Open Policy Agent is not ready: {err:#}"),
+            StartupError::Tls(err) => write!(f, "Could not load TLS configuration: {err:#}"),
+            StartupError::Bind(err) => write!(f, "Could not bind socket: {err:#}"),
+            StartupError::Serve(err) => write!(f, "Server failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StartupError {}
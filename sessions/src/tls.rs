@@ -0,0 +1,65 @@
+use axum_server::tls_rustls::RustlsConfig;
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use tracing::{info, instrument, warn};
+
+/// A TLS certificate chain and private key, read from PEM files
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    /// The path of the PEM encoded certificate chain
+    cert: PathBuf,
+    /// The path of the PEM encoded private key
+    key: PathBuf,
+}
+
+impl TlsFiles {
+    /// Creates a [`TlsFiles`] reading the certificate chain and private key from the paths
+    pub fn new(cert: PathBuf, key: PathBuf) -> Self {
+        Self { cert, key }
+    }
+
+    /// The most recent modification time of the certificate chain and private key files
+    async fn modified(&self) -> io::Result<SystemTime> {
+        let cert = tokio::fs::metadata(&self.cert).await?.modified()?;
+        let key = tokio::fs::metadata(&self.key).await?.modified()?;
+        Ok(cert.max(key))
+    }
+
+    /// Loads the certificate chain and private key into a [`RustlsConfig`]
+    #[instrument]
+    pub async fn load(&self) -> io::Result<RustlsConfig> {
+        info!("Loading TLS certificate from {}", self.cert.display());
+        RustlsConfig::from_pem_file(&self.cert, &self.key).await
+    }
+
+    /// Reloads the certificate chain and private key into the [`RustlsConfig`] whenever either file is modified, checking
+    /// at the interval, such that renewed certificates are served without a restart
+    pub async fn watch(self, config: RustlsConfig, interval: Duration) {
+        let mut loaded = self.modified().await.ok();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let modified = match self.modified().await {
+                Ok(modified) => modified,
+                Err(err) => {
+                    warn!("Could not check TLS certificate for changes: {err}");
+                    continue;
+                }
+            };
+            if loaded == Some(modified) {
+                continue;
+            }
+            match config.reload_from_pem_file(&self.cert, &self.key).await {
+                Ok(()) => {
+                    info!("Reloaded TLS certificate from {}", self.cert.display());
+                    loaded = Some(modified);
+                }
+                Err(err) => warn!("Could not reload TLS certificate: {err}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Helpers shared by the end-to-end tests and benchmarks, which serve the GraphQL API from the built binary or route
+//! requests to it in process
+//!
+//! Not every target which includes these uses each of them
+#![allow(dead_code)]
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderName, Request, StatusCode},
+    Router,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use sessions::{
+    api_key::ApiKeys,
+    database::Databases,
+    dev,
+    error_reporting::ErrorReporting,
+    fixtures::Fixtures,
+    graphql::{root_schema_builder, RootSchema, SessionCache},
+    grpc::{proto::session_service_client::SessionServiceClient, SessionGrpc},
+    mock_opa::MockOpa,
+    opa::{
+        BreakerMode, CircuitBreaker, ForwardClaims, OpaClient, OpaTls, PublicPolicy, RetryPolicy,
+    },
+    route_handlers::GraphQLHandler,
+    router::setup_router,
+    safelist::Safelist,
+};
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::net::TcpListener;
+use tonic::transport::{server::TcpIncoming, Channel};
+use tower::ServiceExt;
+
+/// How long to wait for a dependency to accept connections, and for the server to become ready
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The path of the fixture file with the name
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The server, serving the GraphQL API from a child process which is killed when dropped
+pub struct Server {
+    /// The process of the server
+    process: Child,
+    /// The URL of the GraphQL endpoint of the server
+    endpoint: String,
+    /// The client with which queries are made
+    client: reqwest::Client,
+}
+
+impl Server {
+    /// Serves the GraphQL API on an ephemeral local port, with the arguments to `sessions serve`, and waits for it to
+    /// become ready
+    pub async fn start<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> Self {
+        let port = ephemeral_port().await;
+        let process = Command::new(env!("CARGO_BIN_EXE_sessions"))
+            .args(["serve", "--listen", &format!("127.0.0.1:{port}")])
+            .args(args.into_iter().map(|arg| arg.as_ref().to_string()))
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Server should start");
+        let server = Self {
+            process,
+            endpoint: format!("http://127.0.0.1:{port}/"),
+            client: reqwest::Client::new(),
+        };
+        server.ready().await;
+        server
+    }
+
+    /// Waits for the server to report that it is ready to serve requests
+    async fn ready(&self) {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        let readiness = format!("{}readyz", self.endpoint);
+        while Instant::now() < deadline {
+            match self.client.get(&readiness).send().await {
+                Ok(response) if response.status().is_success() => return,
+                _ => tokio::time::sleep(Duration::from_millis(250)).await,
+            }
+        }
+        panic!("Server did not become ready within {STARTUP_TIMEOUT:?}");
+    }
+
+    /// Executes the GraphQL query and returns the response
+    pub async fn execute(&self, query: &str) -> Value {
+        self.client
+            .post(&self.endpoint)
+            .json(&json!({ "query": query }))
+            .send()
+            .await
+            .expect("Query should be sent")
+            .json::<Value>()
+            .await
+            .expect("Response should be JSON")
+    }
+
+    /// Executes the GraphQL query and returns the data of the response, panicking if it has errors
+    pub async fn query(&self, query: &str) -> Value {
+        let response = self.execute(query).await;
+        assert_eq!(response["errors"], Value::Null, "{response:#}");
+        response["data"].clone()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.process.kill().ok();
+        self.process.wait().ok();
+    }
+}
+
+/// Finds a local port which is free to be bound by the server
+async fn ephemeral_port() -> u16 {
+    TcpListener::bind(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)))
+        .await
+        .expect("Ephemeral port should be bindable")
+        .local_addr()
+        .expect("Listener should have an address")
+        .port()
+}
+
+/// The GraphQL API, serving the development database and authorized by a mock of the Open Policy Agent, routed in process
+/// without a listener, such that tests need not build or spawn the binary
+pub struct App {
+    /// The router of the GraphQL API
+    router: Router,
+    /// The handler through which the gRPC API executes queries
+    handler: GraphQLHandler<RootSchema>,
+}
+
+impl App {
+    /// Routes the GraphQL API with the default configuration of `sessions serve --dev --mock-opa`, with the mock rules file
+    /// if provided
+    pub async fn start(mock_opa_rules: Option<&Path>) -> Self {
+        Self::build(mock_opa_rules, None, None).await
+    }
+
+    /// Routes the GraphQL API as [`App::start`], authenticating machine clients by the keys of the file in the
+    /// `x-api-key` header
+    pub async fn start_with_api_keys(mock_opa_rules: Option<&Path>, api_keys: &Path) -> Self {
+        Self::build(mock_opa_rules, None, Some(api_keys)).await
+    }
+
+    /// Routes the GraphQL API as [`App::start`], permitting only the operations of the persisted query manifest if
+    /// provided
+    pub async fn start_with_manifest(
+        mock_opa_rules: Option<&Path>,
+        persisted_query_manifest: Option<&Path>,
+    ) -> Self {
+        Self::build(mock_opa_rules, persisted_query_manifest, None).await
+    }
+
+    /// Routes the GraphQL API with the mock rules, persisted query manifest and API keys files, if provided
+    async fn build(
+        mock_opa_rules: Option<&Path>,
+        persisted_query_manifest: Option<&Path>,
+        api_keys: Option<&Path>,
+    ) -> Self {
+        let retry = RetryPolicy {
+            attempts: 1,
+            backoff: Duration::ZERO,
+            jitter: 0.0,
+        };
+        let database = Databases::new(
+            dev::seed_database(&Fixtures::development())
+                .await
+                .expect("Development database should be seeded"),
+            Vec::new(),
+            retry.clone(),
+        );
+        let mock = match mock_opa_rules {
+            Some(path) => MockOpa::load(path).expect("Mock rules should be valid"),
+            None => MockOpa::default(),
+        };
+        let opa_client = OpaClient::new(
+            mock.serve().await.expect("Mock should be served"),
+            Duration::from_secs(5),
+            retry,
+            CircuitBreaker::new(5, Duration::from_secs(30), BreakerMode::Deny),
+            &OpaTls {
+                client_identity: None,
+                ca_bundle: None,
+            },
+            "system/main".to_string(),
+            None,
+        )
+        .expect("Open Policy Agent client should be created");
+        let schema_builder = root_schema_builder()
+            .data(database)
+            .data(opa_client)
+            .data(PublicPolicy("public/read".to_string()))
+            .data(ForwardClaims(false))
+            .data(None::<SessionCache>)
+            .extension(ErrorReporting);
+        let schema = match persisted_query_manifest {
+            Some(path) => {
+                schema_builder.extension(Safelist::load(path).expect("Manifest should be valid"))
+            }
+            None => schema_builder,
+        }
+        .finish();
+        let api_keys = api_keys.map(|path| ApiKeys::load(path).expect("API keys should be valid"));
+        let handler = GraphQLHandler::new(schema)
+            .with_api_keys(api_keys.map(Arc::new), HeaderName::from_static("x-api-key"));
+        let router = setup_router(
+            handler.clone(),
+            "/",
+            None,
+            false,
+            1024 * 1024,
+            Duration::from_secs(30),
+            None,
+            None,
+        );
+        Self { router, handler }
+    }
+
+    /// Serves the gRPC API on an ephemeral local port and returns a client of it
+    pub async fn grpc(&self) -> SessionServiceClient<Channel> {
+        let listener = TcpListener::bind(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)))
+            .await
+            .expect("Ephemeral port should be bindable");
+        let address = listener
+            .local_addr()
+            .expect("Listener should have an address");
+        let server = tonic::transport::Server::builder()
+            .add_service(SessionGrpc::server(self.handler.clone()))
+            .serve_with_incoming(
+                TcpIncoming::from_listener(listener, true, None)
+                    .expect("Listener should have an address"),
+            );
+        tokio::spawn(server);
+        SessionServiceClient::connect(format!("http://{address}"))
+            .await
+            .expect("gRPC API should accept connections")
+    }
+
+    /// Executes the GraphQL query and returns the response
+    pub async fn execute(&self, query: &str) -> Value {
+        let request = Request::post("/")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "query": query }).to_string()))
+            .expect("Request should be valid");
+        let (_, body) = self.respond(request).await;
+        serde_json::from_str(&body).expect("Response should be JSON")
+    }
+
+    /// Makes the GraphQL subscription over Server-Sent Events and returns the first response it yields
+    pub async fn subscribe(&self, query: &str) -> Value {
+        let request = Request::post("/sse")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json!({ "query": query }).to_string()))
+            .expect("Request should be valid");
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Router should be infallible");
+        let mut body = response.into_body().into_data_stream();
+        let mut events = String::new();
+        loop {
+            let chunk = tokio::time::timeout(STARTUP_TIMEOUT, body.next())
+                .await
+                .expect("Subscription should yield a response")
+                .expect("Subscription should not end before yielding a response")
+                .expect("Response body should be read");
+            events.push_str(std::str::from_utf8(&chunk).expect("Response should be UTF-8"));
+            let next = events
+                .split("\n\n")
+                .filter(|event| event.lines().any(|line| line == "event: next"))
+                .find_map(|event| event.lines().find_map(|line| line.strip_prefix("data: ")));
+            if let Some(data) = next {
+                return serde_json::from_str(data).expect("Response should be JSON");
+            }
+        }
+    }
+
+    /// Makes a GET request of the path and returns the status and JSON body of the response
+    pub async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let (status, body) = self.get_text(path).await;
+        (
+            status,
+            serde_json::from_str(&body).expect("Response should be JSON"),
+        )
+    }
+
+    /// Makes a GET request of the path and returns the status and body of the response
+    pub async fn get_text(&self, path: &str) -> (StatusCode, String) {
+        let request = Request::get(path)
+            .body(Body::empty())
+            .expect("Request should be valid");
+        self.respond(request).await
+    }
+
+    /// Makes a GET request of the path with the headers and returns the status, headers and body of the response
+    pub async fn get_with_headers(
+        &self,
+        path: &str,
+        headers: &[(HeaderName, &str)],
+    ) -> (StatusCode, HeaderMap, String) {
+        let mut request = Request::get(path);
+        for (name, value) in headers {
+            request = request.header(name, *value);
+        }
+        self.respond_with_headers(
+            request
+                .body(Body::empty())
+                .expect("Request should be valid"),
+        )
+        .await
+    }
+
+    /// Routes the request and returns the status and body of the response
+    async fn respond(&self, request: Request<Body>) -> (StatusCode, String) {
+        let (status, _, body) = self.respond_with_headers(request).await;
+        (status, body)
+    }
+
+    /// Routes the request and returns the status, headers and body of the response
+    async fn respond_with_headers(
+        &self,
+        request: Request<Body>,
+    ) -> (StatusCode, HeaderMap, String) {
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Router should be infallible");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Response body should be read");
+        (
+            status,
+            headers,
+            String::from_utf8(body.to_vec()).expect("Response should be UTF-8"),
+        )
+    }
+}
@@ -0,0 +1,32 @@
+-- A partial mirror of ISPyB in Postgres, holding only the proposals and sessions of the fixtures in ispyb.yaml, with the
+-- column types to which the entities are decoded
+CREATE TYPE state AS ENUM ('Open', 'Closed', 'Cancelled');
+
+CREATE TABLE "Proposal" (
+    "proposalId" oid PRIMARY KEY,
+    "personId" oid NOT NULL,
+    "title" varchar(200),
+    "proposalCode" varchar(45),
+    "proposalNumber" varchar(45),
+    "proposalType" varchar(2),
+    "state" state
+);
+
+CREATE TABLE "BLSession" (
+    "sessionId" oid PRIMARY KEY,
+    "proposalId" oid NOT NULL REFERENCES "Proposal",
+    "startDate" timestamp,
+    "endDate" timestamp,
+    "visit_number" oid,
+    "beamLineName" varchar(45),
+    "usedFlag" "char"
+);
+
+INSERT INTO "Proposal" VALUES
+    (1, 1, 'Structures of lysozyme', 'cm', '31111', 'MX', 'Open'),
+    (2, 2, 'Thermolysin soaks', 'mx', '23694', 'MX', 'Closed');
+
+INSERT INTO "BLSession" VALUES
+    (1, 1, '2024-01-08 09:00', '2024-01-09 09:00', 1, 'i03', 1::"char"),
+    (2, 1, '2024-02-12 09:00', '2024-02-13 09:00', 2, 'i04', 0::"char"),
+    (3, 2, '2024-03-04 09:00', '2024-03-05 09:00', 1, 'i03', NULL);
@@ -0,0 +1,126 @@
+//! Tests of the gRPC API, served in process against the development database and authorized by a mock of the Open Policy
+//! Agent which denies access to proposal mx23694
+
+mod common;
+
+use common::{fixture, App};
+use sessions::grpc::proto::{GetSessionRequest, ListSessionsRequest, Proposal, Session};
+use std::path::Path;
+use tonic::Code;
+
+/// Routes the API, denying access to proposal mx23694
+async fn app() -> App {
+    App::start(Some(Path::new(&fixture("mock_opa.yaml")))).await
+}
+
+#[tokio::test]
+async fn session_is_retrieved_with_its_proposal() {
+    let mut client = app().await.grpc().await;
+    let session = client
+        .get_session(GetSessionRequest {
+            proposal_code: "cm".to_string(),
+            proposal_number: 31111,
+            visit: 1,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        session,
+        Session {
+            id: 1,
+            visit: 1,
+            start: Some("2024-05-02T09:00:00+00:00".to_string()),
+            end: Some("2024-05-03T09:00:00+00:00".to_string()),
+            beamline: Some("i03".to_string()),
+            proposal: Some(Proposal {
+                code: Some("cm".to_string()),
+                number: Some(31111),
+                state: Some("OPEN".to_string()),
+            }),
+        }
+    );
+}
+
+#[tokio::test]
+async fn unknown_session_is_not_found() {
+    let mut client = app().await.grpc().await;
+    let status = client
+        .get_session(GetSessionRequest {
+            proposal_code: "cm".to_string(),
+            proposal_number: 31111,
+            visit: 99,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn denied_session_is_permission_denied() {
+    let mut client = app().await.grpc().await;
+    let status = client
+        .get_session(GetSessionRequest {
+            proposal_code: "mx".to_string(),
+            proposal_number: 23694,
+            visit: 1,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(status.message(), "Access denied: Not a member of mx23694");
+}
+
+#[tokio::test]
+async fn sessions_are_listed_without_those_denied() {
+    let mut client = app().await.grpc().await;
+    let listed = client
+        .list_sessions(ListSessionsRequest::default())
+        .await
+        .unwrap()
+        .into_inner();
+    let ids = listed
+        .sessions
+        .iter()
+        .map(|session| session.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2, 5]);
+    let filtered = client
+        .list_sessions(ListSessionsRequest {
+            proposal_code: Some("cm".to_string()),
+            proposal_number: Some(31111),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(filtered.sessions.len(), 2);
+}
+
+#[tokio::test]
+async fn sessions_are_listed_a_page_at_a_time() {
+    let mut client = app().await.grpc().await;
+    let page = client
+        .list_sessions(ListSessionsRequest {
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let ids = page
+        .sessions
+        .iter()
+        .map(|session| session.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![2]);
+    let status = client
+        .list_sessions(ListSessionsRequest {
+            limit: Some(1001),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
@@ -0,0 +1,80 @@
+//! Tests of the entity tags of GraphQL queries made over GET, with which clients and intermediary caches revalidate cached
+//! responses, routed in process against the development database and authenticating machine clients by API key
+
+mod common;
+
+use axum::http::{
+    header::{ETAG, IF_NONE_MATCH, VARY},
+    HeaderName, StatusCode,
+};
+use common::{fixture, App};
+use std::path::Path;
+
+/// The path of a GET request counting the sessions
+const SESSION_COUNT: &str = "/?query=%7BsessionCount%7D";
+
+/// The headers by which requests may be authenticated, each of which a cached response varies by
+const VARY_HEADERS: &str = "authorization, cookie, x-api-key";
+
+/// Routes the API, permitting all operations and accepting the `kiosk-key` API key
+async fn app() -> App {
+    App::start_with_api_keys(None, Path::new(&fixture("api_keys.json"))).await
+}
+
+#[tokio::test]
+async fn query_over_get_is_tagged() {
+    let (status, headers, body) = app().await.get_with_headers(SESSION_COUNT, &[]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"data":{"sessionCount":5}}"#);
+    assert!(headers.contains_key(ETAG));
+    assert_eq!(headers[VARY], VARY_HEADERS);
+}
+
+#[tokio::test]
+async fn query_with_api_key_varies_by_it() {
+    let (status, headers, body) = app()
+        .await
+        .get_with_headers(
+            SESSION_COUNT,
+            &[(HeaderName::from_static("x-api-key"), "kiosk-key")],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"data":{"sessionCount":5}}"#);
+    assert_eq!(headers[VARY], VARY_HEADERS);
+}
+
+#[tokio::test]
+async fn matching_tag_is_not_modified() {
+    let app = app().await;
+    let (_, headers, _) = app.get_with_headers(SESSION_COUNT, &[]).await;
+    let etag = headers[ETAG].to_str().unwrap();
+    let (status, revalidated, body) = app
+        .get_with_headers(SESSION_COUNT, &[(IF_NONE_MATCH, etag)])
+        .await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(body, "");
+    assert_eq!(revalidated[ETAG], etag);
+}
+
+#[tokio::test]
+async fn any_tag_is_not_modified() {
+    let (status, _, body) = app()
+        .await
+        .get_with_headers(SESSION_COUNT, &[(IF_NONE_MATCH, "*")])
+        .await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(body, "");
+}
+
+#[tokio::test]
+async fn stale_tag_is_served_in_full() {
+    let app = app().await;
+    let (_, headers, _) = app.get_with_headers(SESSION_COUNT, &[]).await;
+    let (status, revalidated, body) = app
+        .get_with_headers(SESSION_COUNT, &[(IF_NONE_MATCH, "\"stale\"")])
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, r#"{"data":{"sessionCount":5}}"#);
+    assert_eq!(revalidated[ETAG], headers[ETAG]);
+}
@@ -0,0 +1,122 @@
+//! Tests of the REST endpoints serving single sessions as JSON and lists of them as CSV and TSV, routed in process against the development database and
+//! authorized by a mock of the Open Policy Agent which denies access to proposal mx23694
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{fixture, App};
+use serde_json::json;
+use std::path::Path;
+
+/// Routes the API, denying access to proposal mx23694
+async fn app() -> App {
+    App::start(Some(Path::new(&fixture("mock_opa.yaml")))).await
+}
+
+#[tokio::test]
+async fn session_is_served_as_json() {
+    let (status, body) = app().await.get("/sessions/cm31111/1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({
+            "id": 1,
+            "visit": 1,
+            "beamline": "i03",
+            "start": "2024-05-02T09:00:00+00:00",
+            "end": "2024-05-03T09:00:00+00:00",
+            "proposal": { "code": "cm", "number": 31111, "state": "OPEN" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn unknown_session_is_not_found() {
+    let (status, _) = app().await.get("/sessions/cm31111/99").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn denied_session_is_forbidden() {
+    let (status, body) = app().await.get("/sessions/mx23694/1").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        body["errors"][0]["extensions"]["reason"],
+        "Not a member of mx23694"
+    );
+}
+
+#[tokio::test]
+async fn malformed_proposal_is_rejected() {
+    let (status, _) = app().await.get("/sessions/31111/1").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn sessions_are_exported_as_csv() {
+    let (status, body) = app().await.get_text("/sessions.csv").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        "id,proposal,visit,beamline,start,end,state\n\
+         1,cm31111,1,i03,2024-05-02T09:00:00+00:00,2024-05-03T09:00:00+00:00,OPEN\n\
+         2,cm31111,2,i04,2024-05-09T09:00:00+00:00,2024-05-10T09:00:00+00:00,OPEN\n\
+         5,sw30864,1,b07,2024-05-30T09:00:00+00:00,2024-05-31T09:00:00+00:00,OPEN\n"
+    );
+}
+
+#[tokio::test]
+async fn sessions_of_proposal_are_exported_as_tsv() {
+    let (status, body) = app().await.get_text("/sessions.tsv?proposal=cm31111").await;
+    assert_eq!(status, StatusCode::OK);
+    let rows = body.lines().collect::<Vec<_>>();
+    assert_eq!(rows[0], "id\tproposal\tvisit\tbeamline\tstart\tend\tstate");
+    assert_eq!(rows.len(), 3);
+    assert!(rows[1..].iter().all(|row| row.contains("\tcm31111\t")));
+}
+
+#[tokio::test]
+async fn sessions_are_exported_a_page_at_a_time() {
+    for (app, expected) in [
+        (app().await, vec!["2", "5"]),
+        (App::start(None).await, vec!["2", "3"]),
+    ] {
+        let (status, body) = app.get_text("/sessions.csv?limit=2&offset=1").await;
+        assert_eq!(status, StatusCode::OK);
+        let ids = body
+            .lines()
+            .skip(1)
+            .map(|row| row.split(',').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+    }
+    let (status, _) = app().await.get_text("/sessions.csv?limit=1001").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn denied_sessions_are_not_exported() {
+    let (status, body) = app().await.get_text("/sessions.csv?proposal=mx23694").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.lines().count(), 1);
+}
+
+#[tokio::test]
+async fn sessions_are_served_with_a_persisted_query_manifest() {
+    let app = App::start_with_manifest(
+        Some(Path::new(&fixture("mock_opa.yaml"))),
+        Some(Path::new(&fixture("manifest.json"))),
+    )
+    .await;
+    let (status, body) = app.get("/sessions/cm31111/1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["beamline"], "i03");
+    let (status, body) = app.get_text("/sessions.csv").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.lines().count(), 4);
+    let response = app.execute("{ sessions { id } }").await;
+    assert_eq!(
+        response["errors"][0]["message"],
+        "Operation is not in the safelist"
+    );
+}
@@ -0,0 +1,20 @@
+//! Snapshot tests which execute each query of the corpus in `queries` against the seeded development database and
+//! compare the responses to those recorded in `snapshots`, such that changes to the shape of the API are deliberate
+//!
+//! Queries are authorized by a mock of the Open Policy Agent which denies access to proposal mx23694. After an intended
+//! change, review and record the new responses with `cargo insta review`
+
+mod common;
+
+use common::{fixture, App};
+use std::{fs, path::Path};
+
+#[test]
+fn responses_match_snapshots() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let app = runtime.block_on(App::start(Some(Path::new(&fixture("mock_opa.yaml")))));
+    insta::glob!("queries/*.graphql", |path| {
+        let query = fs::read_to_string(path).unwrap();
+        insta::assert_json_snapshot!(runtime.block_on(app.execute(&query)));
+    });
+}
@@ -0,0 +1,42 @@
+//! Tests of the subscriptions served as Server-Sent Events, routed in process against the development database and
+//! authorized by a mock of the Open Policy Agent which denies access to proposal mx23694
+
+mod common;
+
+use common::{fixture, App};
+use serde_json::json;
+use std::path::Path;
+
+/// Routes the API, denying access to proposal mx23694
+async fn app() -> App {
+    App::start(Some(Path::new(&fixture("mock_opa.yaml")))).await
+}
+
+#[tokio::test]
+async fn watched_session_is_yielded_once_subscribed() {
+    let response = app()
+        .await
+        .subscribe(
+            "subscription { session(proposalCode: \"cm\", proposalNumber: 31111, visit: 1) { id beamline } }",
+        )
+        .await;
+    assert_eq!(
+        response,
+        json!({ "data": { "session": { "id": 1, "beamline": "i03" } } })
+    );
+}
+
+#[tokio::test]
+async fn watching_denied_session_is_forbidden() {
+    let response = app()
+        .await
+        .subscribe(
+            "subscription { session(proposalCode: \"mx\", proposalNumber: 23694, visit: 1) { id } }",
+        )
+        .await;
+    assert_eq!(response["data"], json!(null));
+    assert_eq!(
+        response["errors"][0]["extensions"]["reason"],
+        "Not a member of mx23694"
+    );
+}
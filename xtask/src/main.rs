@@ -0,0 +1,932 @@
+use clap::{Args, Parser};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use sea_orm_codegen::{
+    DateTimeCrate, EntityTransformer, EntityWriterContext, OutputFile, WithSerde,
+};
+use sea_schema::{
+    mysql::{
+        self,
+        def::{
+            CharSet, Collation, ForeignKeyAction, ForeignKeyInfo, IndexInfo, IndexOrder, IndexPart,
+            IndexType, StorageEngine, SystemInfo, TableDef, TableInfo,
+        },
+        query::{ColumnQueryResult, SchemaQueryBuilder},
+    },
+    postgres,
+    sea_query::TableCreateStatement,
+    Name,
+};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use sqlx::{MySql, Pool, Postgres};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use url::Url;
+
+type TableSpecs = BTreeMap<String, Vec<ColumnSpec>>;
+
+/// A column to include, named alone or along with the options with which it is generated
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ColumnSpec {
+    Name(String),
+    Options(BTreeMap<String, ColumnOptions>),
+}
+
+impl ColumnSpec {
+    fn name(&self) -> &str {
+        match self {
+            ColumnSpec::Name(name) => name,
+            ColumnSpec::Options(options) => options.keys().next().unwrap(),
+        }
+    }
+
+    fn options(&self) -> Option<&ColumnOptions> {
+        match self {
+            ColumnSpec::Name(_) => None,
+            ColumnSpec::Options(options) => options.values().next(),
+        }
+    }
+}
+
+/// The options with which a column is generated
+#[derive(Debug, Deserialize)]
+struct ColumnOptions {
+    /// The name from which the field is generated in place of that of the column
+    rename: Option<String>,
+    /// The MySQL type, e.g. `int(10) unsigned` or `datetime(6)`, from which the field is generated in place of that of the
+    /// column
+    #[serde(rename = "type")]
+    column_type: Option<String>,
+    /// The enumeration the values of the column are mapped to
+    #[serde(flatten)]
+    enumeration: Option<EnumSpec>,
+    /// Whether the column is part of the primary key, in place of that of the table, as views have none
+    #[serde(default)]
+    primary_key: bool,
+}
+
+/// The tables selected from the schema, and the names of those which are views
+#[derive(Debug)]
+struct Selection {
+    tables: Vec<TableCreateStatement>,
+    views: Vec<String>,
+}
+
+/// An enumeration generated for a string or integer column, from the name of each variant to its value in the column
+#[derive(Debug, Deserialize)]
+struct EnumSpec {
+    #[serde(rename = "enum")]
+    name: String,
+    values: Mapping,
+}
+
+/// Derives the GraphQL enumeration of every generated enumeration when the models are built with the `graphql` feature
+const GRAPHQL_ENUM_ATTRIBUTE: &str =
+    r#"cfg_attr(feature = "graphql", derive(async_graphql::Enum))"#;
+
+/// Derives serialization of every generated model and enumeration when the models are built with the `serde` feature
+const SERDE_ATTRIBUTE: &str =
+    r#"cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))"#;
+
+const ACTIVE_ENUMS_FILE: &str = "sea_orm_active_enums.rs";
+
+const TABLES_SPECS_PATH: &str = "tables.yaml";
+
+const SCHEMA_DUMP_PATH: &str = "schema.sql";
+
+const POSTGRES_SCHEMA: &str = "public";
+
+/// The comment MySQL reports for each view in `information_schema.tables`
+const VIEW_COMMENT: &str = "VIEW";
+
+fn load_table_specs(path: &Path) -> TableSpecs {
+    serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn includes_column(columns: &[ColumnSpec], name: &str) -> bool {
+    columns.iter().any(|column| column.name() == name)
+}
+
+/// Lists the tables and columns of the specs which are absent from the discovered tables and their columns, on which
+/// the entities depend but which would otherwise be silently left out of them
+fn missing_columns(specs: &TableSpecs, discovered: &BTreeMap<&str, Vec<&str>>) -> Vec<String> {
+    specs
+        .iter()
+        .flat_map(|(table, columns)| match discovered.get(table.as_str()) {
+            Some(found) => columns
+                .iter()
+                .filter(|column| !found.contains(&column.name()))
+                .map(|column| format!("column {table}.{}", column.name()))
+                .collect(),
+            None => vec![format!("table {table}")],
+        })
+        .collect()
+}
+
+async fn discover_mysql(database_url: &Url, specs: &TableSpecs) -> Result<Selection, Vec<String>> {
+    let database_name = database_url.path_segments().unwrap().next().unwrap();
+    let connection = Pool::<MySql>::connect(database_url.as_str()).await.unwrap();
+
+    let schema_discovery =
+        mysql::discovery::SchemaDiscovery::new(connection.clone(), database_name);
+    let mut tables = schema_discovery.discover().await.unwrap().tables;
+
+    // Views are not discovered alongside tables, so any table of the specs not found is discovered as a view
+    let mut view_discovery = mysql::discovery::SchemaDiscovery::new(connection, database_name);
+    view_discovery.query = SchemaQueryBuilder::new(view_discovery.discover_system().await.unwrap());
+    let views = specs
+        .keys()
+        .filter(|name| !tables.iter().any(|def| &def.info.name == *name))
+        .cloned()
+        .collect::<Vec<_>>();
+    for name in views {
+        let info = TableInfo {
+            comment: VIEW_COMMENT.to_string(),
+            ..table_info(&name)
+        };
+        tables.push(view_discovery.discover_table(info).await.unwrap());
+    }
+    select_mysql_tables(tables, specs)
+}
+
+fn select_mysql_tables(
+    tables: Vec<TableDef>,
+    specs: &TableSpecs,
+) -> Result<Selection, Vec<String>> {
+    let discovered = tables
+        .iter()
+        .map(|def| {
+            let columns = def.columns.iter().map(|column| column.name.as_str());
+            (def.info.name.as_str(), columns.collect())
+        })
+        .collect();
+    let missing = missing_columns(specs, &discovered);
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+    let views = tables
+        .iter()
+        .filter(|def| def.info.comment == VIEW_COMMENT && specs.contains_key(&def.info.name))
+        .map(|def| def.info.name.clone())
+        .collect();
+    let tables = tables
+        .into_iter()
+        .filter_map(|mut def| {
+            let columns = specs.get(&def.info.name)?;
+            let primary_key = columns
+                .iter()
+                .filter(|column| column.options().is_some_and(|options| options.primary_key))
+                .map(|column| column.name().to_string())
+                .collect::<Vec<_>>();
+            if !primary_key.is_empty() {
+                def.indexes.retain(|index| index.name != "PRIMARY");
+                def.indexes
+                    .push(index("PRIMARY".to_string(), true, primary_key));
+            }
+            def.foreign_keys
+                .retain(|fk| specs.contains_key(&fk.referenced_table));
+            def.columns
+                .retain(|column| includes_column(columns, &column.name));
+            for column in &mut def.columns {
+                let column_type = columns
+                    .iter()
+                    .find(|spec| spec.name() == column.name)
+                    .and_then(ColumnSpec::options)
+                    .and_then(|options| options.column_type.as_ref());
+                if let Some(column_type) = column_type {
+                    let tokens = split_tokens(&format!("`{}` {column_type}", column.name));
+                    column.col_type = parse_column(&tokens).parse(&system_info()).col_type;
+                }
+            }
+            Some(def.write())
+        })
+        .collect();
+    Ok(Selection { tables, views })
+}
+
+async fn discover_postgres(
+    database_url: &Url,
+    specs: &TableSpecs,
+) -> Result<Selection, Vec<String>> {
+    let connection = Pool::<Postgres>::connect(database_url.as_str())
+        .await
+        .unwrap();
+
+    let schema_discovery = postgres::discovery::SchemaDiscovery::new(connection, POSTGRES_SCHEMA);
+    let schema = schema_discovery.discover().await.unwrap();
+    let discovered = schema
+        .tables
+        .iter()
+        .map(|def| {
+            let columns = def.columns.iter().map(|column| column.name.as_str());
+            (def.info.name.as_str(), columns.collect())
+        })
+        .collect();
+    let missing = missing_columns(specs, &discovered);
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+    let tables = schema
+        .tables
+        .into_iter()
+        .filter_map(|mut def| {
+            let columns = specs.get(&def.info.name)?;
+            if let Some(column) = columns.iter().find(|column| {
+                column
+                    .options()
+                    .is_some_and(|options| options.column_type.is_some())
+            }) {
+                panic!(
+                    "Cannot override the type of {}.{}, types may only be overridden in MySQL schemas",
+                    def.info.name,
+                    column.name()
+                );
+            }
+            def.reference_constraints
+                .retain(|reference| specs.contains_key(&reference.table));
+            def.columns
+                .retain(|column| includes_column(columns, &column.name));
+            Some(def.write())
+        })
+        .collect();
+    Ok(Selection {
+        tables,
+        views: Vec::new(),
+    })
+}
+
+fn split_tokens(definition: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quote = None;
+    let mut depth = 0;
+    for character in definition.chars() {
+        match (quote, character) {
+            (Some(open), _) if character == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(character),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, _) if character.is_whitespace() && depth == 0 => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                continue;
+            }
+            (None, _) => {}
+        }
+        token.push(character);
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn unquote(token: &str) -> String {
+    match token.chars().next() {
+        Some(quote @ ('\'' | '"' | '`')) => token
+            .trim_matches(quote)
+            .replace(&format!("{quote}{quote}"), &quote.to_string()),
+        _ => token.to_string(),
+    }
+}
+
+fn column_list(token: &str) -> Vec<String> {
+    token
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|column| unquote(column.trim().split('(').next().unwrap().trim()))
+        .collect()
+}
+
+/// Lowercases the definition outside of quotes, as `information_schema` reports column types, such that the values of
+/// enumerations keep their case
+fn lowercase_unquoted(definition: &str) -> String {
+    let mut quote = None;
+    definition
+        .chars()
+        .map(|character| match (quote, character) {
+            (Some(open), _) if character == open => {
+                quote = None;
+                character
+            }
+            (Some(_), _) => character,
+            (None, '\'' | '"') => {
+                quote = Some(character);
+                character
+            }
+            (None, _) => character.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+fn parse_column(tokens: &[String]) -> ColumnQueryResult {
+    const ATTRIBUTES: &[&str] = &[
+        "NOT",
+        "NULL",
+        "DEFAULT",
+        "AUTO_INCREMENT",
+        "COMMENT",
+        "CHARACTER",
+        "COLLATE",
+        "ON",
+        "GENERATED",
+        "AS",
+    ];
+    let type_length = tokens[1..]
+        .iter()
+        .position(|token| ATTRIBUTES.contains(&token.to_uppercase().as_str()))
+        .unwrap_or(tokens.len() - 1);
+    let mut column = ColumnQueryResult {
+        column_name: unquote(&tokens[0]),
+        column_type: lowercase_unquoted(&tokens[1..=type_length].join(" ")),
+        is_nullable: "YES".to_string(),
+        ..Default::default()
+    };
+    let mut extra = Vec::new();
+    let mut attributes = tokens[type_length + 1..].iter();
+    while let Some(attribute) = attributes.next() {
+        match attribute.to_uppercase().as_str() {
+            "NOT" => {
+                attributes.next();
+                column.is_nullable = "NO".to_string();
+            }
+            "DEFAULT" => {
+                let default = attributes.next().unwrap();
+                column.column_default = match default.to_uppercase().as_str() {
+                    "NULL" => None,
+                    expression if expression.starts_with("CURRENT_TIMESTAMP") => {
+                        extra.push("DEFAULT_GENERATED");
+                        Some("CURRENT_TIMESTAMP".to_string())
+                    }
+                    _ => Some(unquote(default)),
+                };
+            }
+            "AUTO_INCREMENT" => extra.push("auto_increment"),
+            "ON" => {
+                attributes.nth(1);
+                extra.push("on update CURRENT_TIMESTAMP");
+            }
+            "COMMENT" => column.column_comment = unquote(attributes.next().unwrap()),
+            _ => {}
+        }
+    }
+    column.extra = extra.join(" ");
+    column
+}
+
+fn parse_index(name: String, unique: bool, columns: &str) -> IndexInfo {
+    index(name, unique, column_list(columns))
+}
+
+fn index(name: String, unique: bool, columns: Vec<String>) -> IndexInfo {
+    IndexInfo {
+        unique,
+        name,
+        parts: columns
+            .into_iter()
+            .map(|column| IndexPart {
+                column,
+                order: IndexOrder::Ascending,
+                sub_part: None,
+            })
+            .collect(),
+        nullable: false,
+        idx_type: IndexType::BTree,
+        comment: String::new(),
+        functional: false,
+    }
+}
+
+fn parse_foreign_key(tokens: &[String]) -> ForeignKeyInfo {
+    let action = |event: &str| {
+        (0..tokens.len().saturating_sub(2))
+            .find(|&position| {
+                tokens[position].eq_ignore_ascii_case("ON")
+                    && tokens[position + 1].eq_ignore_ascii_case(event)
+            })
+            .map(|position| {
+                let action = tokens[position + 2..]
+                    .iter()
+                    .take_while(|token| !token.eq_ignore_ascii_case("ON"))
+                    .map(|token| token.to_uppercase())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                ForeignKeyAction::from_str(&action).unwrap()
+            })
+            .unwrap_or(ForeignKeyAction::Restrict)
+    };
+    ForeignKeyInfo {
+        name: unquote(&tokens[1]),
+        columns: column_list(&tokens[4]),
+        referenced_table: unquote(&tokens[6]),
+        referenced_columns: column_list(&tokens[7]),
+        on_update: action("UPDATE"),
+        on_delete: action("DELETE"),
+    }
+}
+
+fn system_info() -> SystemInfo {
+    SystemInfo {
+        version: 80000,
+        system: String::new(),
+        suffix: Vec::new(),
+    }
+}
+
+fn table_info(name: &str) -> TableInfo {
+    TableInfo {
+        name: name.to_string(),
+        engine: StorageEngine::InnoDb,
+        auto_increment: None,
+        char_set: CharSet::Utf8Mb4,
+        collation: Collation::Utf8Mb4GeneralCi,
+        comment: String::new(),
+    }
+}
+
+fn parse_table(name: &str, definitions: &[&str]) -> TableDef {
+    let system = system_info();
+    let mut table = TableDef {
+        info: table_info(name),
+        columns: Vec::new(),
+        indexes: Vec::new(),
+        foreign_keys: Vec::new(),
+    };
+    for definition in definitions {
+        let tokens = split_tokens(definition.trim().trim_end_matches(','));
+        match tokens[0].to_uppercase().as_str() {
+            "PRIMARY" => table
+                .indexes
+                .push(parse_index("PRIMARY".to_string(), true, &tokens[2])),
+            "UNIQUE" => table
+                .indexes
+                .push(parse_index(unquote(&tokens[2]), true, &tokens[3])),
+            "KEY" | "INDEX" => {
+                table
+                    .indexes
+                    .push(parse_index(unquote(&tokens[1]), false, &tokens[2]))
+            }
+            "CONSTRAINT" => table.foreign_keys.push(parse_foreign_key(&tokens)),
+            _ => table.columns.push(parse_column(&tokens).parse(&system)),
+        }
+    }
+    table
+}
+
+fn discover_dump(path: &Path, specs: &TableSpecs) -> Result<Selection, Vec<String>> {
+    select_mysql_tables(parse_dump(&fs::read_to_string(path).unwrap()), specs)
+}
+
+fn parse_dump(dump: &str) -> Vec<TableDef> {
+    let mut tables = Vec::new();
+    let mut lines = dump.lines();
+    while let Some(line) = lines.next() {
+        let (rest, view) = match line.strip_prefix("CREATE TABLE ") {
+            Some(rest) => (rest, false),
+            None => match line.strip_prefix("CREATE VIEW ") {
+                Some(rest) => (rest, true),
+                None => continue,
+            },
+        };
+        let name = unquote(rest.trim_end_matches('(').trim());
+        let definitions = lines
+            .by_ref()
+            .take_while(|line| !line.starts_with(')'))
+            .collect::<Vec<_>>();
+        let mut table = parse_table(&name, &definitions);
+        if view {
+            table.info.comment = VIEW_COMMENT.to_string();
+        }
+        tables.push(table);
+    }
+    tables
+}
+
+/// Development tasks of the workspace
+#[derive(Debug, Parser)]
+enum Cli {
+    /// Regenerates the SeaORM entities of the models crate from the database, or from the schema dump if no database is
+    /// configured
+    RegenerateModels(RegenerateModelsArgs),
+}
+
+#[derive(Debug, Args)]
+struct RegenerateModelsArgs {
+    /// The URL of the database from which the tables are discovered, if not set they are parsed from the schema dump
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: Option<Url>,
+    /// Checks the committed entities match those generated, exiting with a non-zero status if they have drifted, rather than
+    /// writing them
+    #[arg(long)]
+    check: bool,
+}
+
+fn models_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("models")
+}
+
+/// Generates the source of each entity, keyed by its file name, or lists the tables and columns of the specs which are
+/// missing from the schema
+async fn generate_entities(
+    database_url: Option<Url>,
+) -> Result<BTreeMap<String, String>, Vec<String>> {
+    let models_dir = models_dir();
+    let specs = load_table_specs(&models_dir.join(TABLES_SPECS_PATH));
+    let selection = match database_url {
+        Some(database_url) => match database_url.scheme() {
+            "postgres" | "postgresql" => discover_postgres(&database_url, &specs).await,
+            _ => discover_mysql(&database_url, &specs).await,
+        },
+        None => discover_dump(&models_dir.join(SCHEMA_DUMP_PATH), &specs),
+    }?;
+
+    let writer_context = EntityWriterContext::new(
+        false,
+        WithSerde::None,
+        true,
+        DateTimeCrate::Chrono,
+        None,
+        true,
+        false,
+        false,
+        vec![],
+        vec![SERDE_ATTRIBUTE.to_string()],
+        vec![],
+        vec![
+            SERDE_ATTRIBUTE.to_string(),
+            GRAPHQL_ENUM_ATTRIBUTE.to_string(),
+        ],
+        false,
+    );
+
+    let mut files = EntityTransformer::transform(selection.tables)
+        .unwrap()
+        .generate(&writer_context)
+        .files
+        .into_iter()
+        .map(|OutputFile { name, content }| (name, format_source(&content)))
+        .collect();
+    apply_column_options(&mut files, &specs);
+    for view in selection.views {
+        make_read_only(entity_file(&mut files, &view), &view);
+    }
+    Ok(files)
+}
+
+/// Replaces the behaviour of the active model of the entity of a view, such that it refuses to be inserted, updated or
+/// deleted
+fn make_read_only(entity: &mut String, view: &str) {
+    let behaviour = format!(
+        r#"#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {{
+    async fn before_save<C>(self, _db: &C, _insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {{
+        Err(DbErr::Custom("{view} is a view and cannot be modified".to_string()))
+    }}
+
+    async fn before_delete<C>(self, _db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {{
+        Err(DbErr::Custom("{view} is a view and cannot be modified".to_string()))
+    }}
+}}
+"#
+    );
+    *entity =
+        format_source(&entity.replace("impl ActiveModelBehavior for ActiveModel {}\n", &behaviour));
+}
+
+/// Applies the options of each column of the specs to the generated entities, mapping each column to its enumeration,
+/// which is written alongside those of the native enumerations of the database, and then renaming its field
+fn apply_column_options(files: &mut BTreeMap<String, String>, specs: &TableSpecs) {
+    for (table, columns) in specs {
+        for column in columns {
+            let Some(options) = column.options() else {
+                continue;
+            };
+            if let Some(spec) = &options.enumeration {
+                map_enum_column(files, table, column.name(), spec);
+            }
+            if let Some(rename) = &options.rename {
+                rename_column(files, table, column.name(), rename);
+            }
+        }
+    }
+    if files.contains_key(ACTIVE_ENUMS_FILE) {
+        let lib = files.get_mut("lib.rs").unwrap();
+        if !lib.contains("pub mod sea_orm_active_enums;") {
+            *lib = format_source(&format!("{lib}pub mod sea_orm_active_enums;\n"));
+        }
+    }
+}
+
+fn entity_file<'a>(files: &'a mut BTreeMap<String, String>, table: &str) -> &'a mut String {
+    files
+        .get_mut(&format!("{}.rs", table.to_snake_case()))
+        .unwrap_or_else(|| panic!("No entity generated for {table}"))
+}
+
+fn field_line(entity: &str, table: &str, column: &str) -> String {
+    let field = format!("    pub {}: ", column.to_snake_case());
+    entity
+        .lines()
+        .find(|line| line.starts_with(&field))
+        .unwrap_or_else(|| panic!("No field generated for {table}.{column}"))
+        .to_string()
+}
+
+/// Replaces the type of the field of the column with the enumeration, which is written alongside those of the native
+/// enumerations of the database
+fn map_enum_column(
+    files: &mut BTreeMap<String, String>,
+    table: &str,
+    column: &str,
+    spec: &EnumSpec,
+) {
+    let entity = entity_file(files, table);
+    let line = field_line(entity, table, column);
+    let field_type = line.split_once(": ").unwrap().1.trim_end_matches(',');
+    let rs_type = field_type
+        .strip_prefix("Option<")
+        .and_then(|field_type| field_type.strip_suffix('>'))
+        .unwrap_or(field_type)
+        .to_string();
+    *entity = format_source(
+        &entity
+            .replacen(
+                "\n\n",
+                &format!("\n\nuse super::sea_orm_active_enums::{};\n", spec.name),
+                1,
+            )
+            .replace(&line, &line.replace(&rs_type, &spec.name)),
+    );
+    let active_enums = files.entry(ACTIVE_ENUMS_FILE.to_string()).or_insert_with(|| {
+        "//! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15\n\nuse sea_orm::entity::prelude::*;\n".to_string()
+    });
+    *active_enums = format_source(&format!("{active_enums}{}", write_enum(spec, &rs_type)));
+}
+
+/// Renames the field of the column, and the variant of the column in the relations referring to it, keeping the name of
+/// the column in the database
+fn rename_column(files: &mut BTreeMap<String, String>, table: &str, column: &str, rename: &str) {
+    let module = table.to_snake_case();
+    let (old_variant, new_variant) = (column.to_upper_camel_case(), rename.to_upper_camel_case());
+    let entity = entity_file(files, table);
+    let line = field_line(entity, table, column);
+    let renamed = line.replacen(&column.to_snake_case(), &rename.to_snake_case(), 1);
+    let mut lines = entity.lines().map(str::to_string).collect::<Vec<_>>();
+    let position = lines
+        .iter()
+        .position(|candidate| *candidate == line)
+        .unwrap();
+    lines[position] = renamed;
+    match lines[position - 1].strip_prefix("    #[sea_orm(") {
+        Some(attributes) if attributes.contains("column_name = ") => {}
+        Some(attributes) => {
+            lines[position - 1] = format!("    #[sea_orm(column_name = {column:?}, {attributes}");
+        }
+        None => lines.insert(
+            position,
+            format!("    #[sea_orm(column_name = {column:?})]"),
+        ),
+    }
+    *entity = format!("{}\n", lines.join("\n")).replace(
+        &format!("\"Column::{old_variant}\""),
+        &format!("\"Column::{new_variant}\""),
+    );
+    for content in files.values_mut() {
+        *content = content.replace(
+            &format!("super::{module}::Column::{old_variant}\""),
+            &format!("super::{module}::Column::{new_variant}\""),
+        );
+    }
+}
+
+/// Writes the enumeration, with values of the string or integer type of the column it is mapped from
+fn write_enum(spec: &EnumSpec, rs_type: &str) -> String {
+    let (db_type, value_attribute) = match rs_type {
+        "String" => ("String(None)", "string_value"),
+        "i8" => ("TinyInteger", "num_value"),
+        "i16" => ("SmallInteger", "num_value"),
+        "i32" => ("Integer", "num_value"),
+        "i64" => ("BigInteger", "num_value"),
+        "u8" => ("TinyUnsigned", "num_value"),
+        "u16" => ("SmallUnsigned", "num_value"),
+        "u32" => ("Unsigned", "num_value"),
+        "u64" => ("BigUnsigned", "num_value"),
+        _ => panic!(
+            "Cannot map a column of {rs_type} to the enumeration {}",
+            spec.name
+        ),
+    };
+    let variants = spec
+        .values
+        .iter()
+        .map(|(variant, value)| {
+            let value = match value {
+                Value::String(value) => format!("{value:?}"),
+                Value::Number(value) => value.to_string(),
+                _ => panic!(
+                    "The values of the enumeration {} must be strings or integers",
+                    spec.name
+                ),
+            };
+            format!(
+                "#[sea_orm({value_attribute} = {value})] {},",
+                variant.as_str().unwrap().to_upper_camel_case()
+            )
+        })
+        .collect::<String>();
+    format!(
+        "#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy)]\n#[sea_orm(rs_type = \"{rs_type}\", db_type = \"{db_type}\")]\n#[{SERDE_ATTRIBUTE}]\n#[{GRAPHQL_ENUM_ATTRIBUTE}]\npub enum {} {{ {variants} }}\n",
+        spec.name
+    )
+}
+
+fn format_source(source: &str) -> String {
+    let mut rustfmt = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    rustfmt
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    let output = rustfmt.wait_with_output().unwrap();
+    assert!(output.status.success(), "rustfmt failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn existing_entities(dir: &Path) -> Vec<String> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".rs"))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Diffs the committed source of each entity against that generated, as the lines removed from and added to those
+/// which differ, such that columns which have changed type or disappeared are apparent
+fn drift(dir: &Path, entities: &BTreeMap<String, String>) -> String {
+    let mut names = existing_entities(dir);
+    names.extend(entities.keys().cloned());
+    names.sort();
+    names.dedup();
+    let mut drift = String::new();
+    for name in names {
+        let committed = fs::read_to_string(dir.join(&name)).unwrap_or_default();
+        let generated = entities.get(&name).map(String::as_str).unwrap_or_default();
+        if committed != generated {
+            drift.push_str(&format!("--- committed/{name}\n+++ generated/{name}\n"));
+            drift.push_str(&diff_lines(&committed, generated));
+        }
+    }
+    drift
+}
+
+/// Lists the lines removed from, prefixed by `-`, and added to, prefixed by `+`, the old text to produce the new
+fn diff_lines(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            common[i][j] = if old_line == new_line {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut diff = String::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .enable_io()
+        .build()
+        .unwrap()
+}
+
+fn regenerate_models(args: RegenerateModelsArgs) {
+    let entities = runtime()
+        .block_on(generate_entities(args.database_url))
+        .unwrap_or_else(|missing| {
+            eprintln!("The schema is missing tables or columns from which entities are generated:");
+            for missing in missing {
+                eprintln!("  {missing}");
+            }
+            std::process::exit(1);
+        });
+    let dir = models_dir().join("src");
+    let stale = existing_entities(&dir)
+        .into_iter()
+        .filter(|name| !entities.contains_key(name))
+        .collect::<Vec<_>>();
+
+    if args.check {
+        let drift = drift(&dir, &entities);
+        if !drift.is_empty() {
+            eprintln!("Entities have drifted from the schema:\n{drift}");
+            eprintln!("Run `cargo xtask regenerate-models` and commit the changes");
+            std::process::exit(1);
+        }
+        println!("Entities are up to date");
+        return;
+    }
+
+    fs::create_dir_all(&dir).unwrap();
+    for name in stale {
+        println!("Removing: {name}");
+        fs::remove_file(dir.join(name)).unwrap();
+    }
+    for (name, content) in entities {
+        println!("Writing: {name}");
+        fs::write(dir.join(name), content).unwrap();
+    }
+}
+
+fn main() {
+    match Cli::parse() {
+        Cli::RegenerateModels(args) => regenerate_models(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diff_lines, drift, generate_entities, models_dir, parse_dump, runtime, select_mysql_tables,
+    };
+
+    #[test]
+    fn committed_entities_match_schema_dump() {
+        let entities = runtime().block_on(generate_entities(None)).unwrap();
+        let drift = drift(&models_dir().join("src"), &entities);
+        assert!(
+            drift.is_empty(),
+            "Entities have drifted from models/schema.sql, run `cargo xtask regenerate-models`:\n{drift}"
+        );
+    }
+
+    #[test]
+    fn missing_table_and_columns_are_reported() {
+        let tables = parse_dump(
+            "CREATE TABLE `Proposal` (\n  `proposalId` int(10) unsigned NOT NULL AUTO_INCREMENT,\n  PRIMARY KEY (`proposalId`)\n) ENGINE=InnoDB;\n",
+        );
+        let specs = serde_yaml::from_str(
+            "Proposal:\n  - proposalId\n  - state:\n      enum: State\n      values:\n        Open: Open\nPerson:\n  - personId\n",
+        )
+        .unwrap();
+        assert_eq!(
+            select_mysql_tables(tables, &specs).unwrap_err(),
+            vec!["table Person", "column Proposal.state"]
+        );
+    }
+
+    #[test]
+    fn changed_column_type_is_diffed() {
+        assert_eq!(
+            diff_lines(
+                "pub struct Model {\n    pub proposal_id: u32,\n    pub title: Option<String>,\n}\n",
+                "pub struct Model {\n    pub proposal_id: i64,\n}\n"
+            ),
+            "-    pub proposal_id: u32,\n-    pub title: Option<String>,\n+    pub proposal_id: i64,\n"
+        );
+    }
+}